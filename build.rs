@@ -0,0 +1,338 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::{env, fs};
+
+use serde::Deserialize;
+
+const CONFIG_FILE: &str = "site.toml";
+/// The paths of `Route` in src/lib.rs other than the not found page, which navigation entries
+/// have to lead to. They're passed on as `config::ROUTES` so a test can hold the two to each other.
+const ROUTES: [&str; 6] = [
+    "/",
+    "/post/:filename",
+    "/posts",
+    "/project/:name",
+    "/projects",
+    "/tag/:tag",
+];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Site {
+    title: String,
+    description: String,
+    url: String,
+    repository: Option<String>,
+    author: Author,
+    home: Home,
+    navigation: Vec<NavigationEntry>,
+    #[serde(default)]
+    social: Vec<SocialLink>,
+    theme: Theme,
+    palettes: BTreeMap<String, Palette>,
+    #[serde(default)]
+    splash: Splash,
+    #[serde(default)]
+    features: Features,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Author {
+    name: String,
+    email: Option<String>,
+    github: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Home {
+    greeting: Vec<String>,
+    bio: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NavigationEntry {
+    title: String,
+    path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SocialLink {
+    label: String,
+    url: String,
+    icon: Option<String>,
+    rel: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Theme {
+    palette: String,
+}
+
+/// Every colour is optional, those left out being taken from the palette picked in `[theme]`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Palette {
+    background: Option<String>,
+    foreground: Option<String>,
+    primary: Option<String>,
+    secondary_light: Option<String>,
+    secondary_dark: Option<String>,
+    error: Option<String>,
+    title_gradient: Option<String>,
+    #[serde(default)]
+    months: Vec<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Splash {
+    enabled: bool,
+    /// In milliseconds.
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Features {
+    search: bool,
+    self_hosted_projects: bool,
+    prerender_math: bool,
+}
+
+impl Default for Splash {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration: 800,
+        }
+    }
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            search: true,
+            self_hosted_projects: false,
+            prerender_math: true,
+        }
+    }
+}
+
+/// Rust's debug formatting of strings happens to be a valid string literal.
+fn literal(value: &str) -> String {
+    format!("{value:?}")
+}
+
+/// Whether `path` is one of `ROUTES`, ignoring a trailing slash like the router does.
+fn is_route(path: &str) -> bool {
+    let segments = |path: &'static str| path.trim_end_matches('/').split('/');
+
+    ROUTES.iter().any(|route| {
+        let path = path.trim_end_matches('/').split('/');
+        path.clone().count() == segments(route).count()
+            && path.zip(segments(route)).all(|(segment, pattern)| {
+                segment == pattern || (pattern.starts_with(':') && !segment.is_empty())
+            })
+    })
+}
+
+fn optional(value: Option<&str>) -> String {
+    match value {
+        None => "None".to_owned(),
+        Some(value) => format!("Some({})", literal(value)),
+    }
+}
+
+impl Palette {
+    fn inherit(&self, default: &Self) -> Self {
+        let inherit = |own: &Option<String>, default: &Option<String>| {
+            own.clone().or_else(|| default.clone())
+        };
+
+        Self {
+            background: inherit(&self.background, &default.background),
+            foreground: inherit(&self.foreground, &default.foreground),
+            primary: inherit(&self.primary, &default.primary),
+            secondary_light: inherit(&self.secondary_light, &default.secondary_light),
+            secondary_dark: inherit(&self.secondary_dark, &default.secondary_dark),
+            error: inherit(&self.error, &default.error),
+            // a gradient is a palette's own flourish, and wouldn't match another's colours
+            title_gradient: self.title_gradient.clone(),
+            months: self.months.clone(),
+        }
+    }
+
+    fn value<'a>(&self, name: &str, color: &'a Option<String>) -> &'a str {
+        match color {
+            None => panic!("{CONFIG_FILE}: the default palette must set `{name}`"),
+            Some(color) => color,
+        }
+    }
+
+    fn color(&self, name: &str, color: &Option<String>) -> String {
+        literal(self.value(name, color))
+    }
+
+    /// The colours as the custom properties the stylesheets use, like `palette_style` in
+    /// src/theme.rs sets them.
+    fn style(&self) -> String {
+        format!(
+            ":root {{ --background: {}; --foreground: {}; --primary: {}; \
+            --secondary-light: {}; --secondary-dark: {}; --error: {}; }}",
+            self.value("background", &self.background),
+            self.value("foreground", &self.foreground),
+            self.value("primary", &self.primary),
+            self.value("secondary_light", &self.secondary_light),
+            self.value("secondary_dark", &self.secondary_dark),
+            self.value("error", &self.error),
+        )
+    }
+
+    fn code(&self, name: &str) -> String {
+        format!(
+            "Palette {{ name: {}, background: {}, foreground: {}, primary: {}, \
+            secondary_light: {}, secondary_dark: {}, error: {}, title_gradient: {}, \
+            months: &{:?} }}",
+            literal(name),
+            self.color("background", &self.background),
+            self.color("foreground", &self.foreground),
+            self.color("primary", &self.primary),
+            self.color("secondary_light", &self.secondary_light),
+            self.color("secondary_dark", &self.secondary_dark),
+            self.color("error", &self.error),
+            optional(self.title_gradient.as_deref()),
+            self.months,
+        )
+    }
+}
+
+/// Generates the constants in `config`, so that the site can be configured without touching its
+/// source and the generator sees the same configuration as the app.
+fn main() {
+    println!("cargo:rerun-if-changed={CONFIG_FILE}");
+
+    let source = fs::read_to_string(CONFIG_FILE)
+        .unwrap_or_else(|error| panic!("couldn't read {CONFIG_FILE}: {error}"));
+    let site: Site = toml::from_str(&source)
+        .unwrap_or_else(|error| panic!("{CONFIG_FILE}: {}", error.message()));
+
+    if let Some(entry) = site.navigation.iter().find(|entry| !entry.path.starts_with('/')) {
+        panic!("{CONFIG_FILE}: navigation entry `{}` must have an absolute path", entry.title);
+    }
+
+    // the router would take anything else to the not found page
+    if let Some(entry) = site.navigation.iter().find(|entry| !is_route(&entry.path)) {
+        panic!("{CONFIG_FILE}: navigation entry `{}` isn't a page of the site", entry.title);
+    }
+
+    let navigation = site
+        .navigation
+        .iter()
+        .map(|entry| {
+            format!(
+                "NavigationEntry {{ title: {}, path: {} }}, ",
+                literal(&entry.title),
+                literal(&entry.path),
+            )
+        })
+        .collect::<String>();
+    let Some(default_palette) = site.palettes.get(&site.theme.palette) else {
+        panic!("{CONFIG_FILE}: there's no palette named `{}`", site.theme.palette);
+    };
+    let palettes = site
+        .palettes
+        .iter()
+        .map(|(name, palette)| (name, palette.inherit(default_palette)))
+        .collect::<Vec<_>>();
+
+    if let Some((name, _)) = palettes
+        .iter()
+        .find(|(_, palette)| palette.months.iter().any(|month| !(1..=12).contains(month)))
+    {
+        panic!("{CONFIG_FILE}: the months of palette `{name}` must be between 1 and 12");
+    }
+
+    let background = default_palette.color("background", &default_palette.background);
+    let primary = default_palette.color("primary", &default_palette.primary);
+    let palette_list = palettes
+        .iter()
+        .map(|(name, palette)| format!("{}, ", palette.code(name)))
+        .collect::<String>();
+
+    let social = site
+        .social
+        .iter()
+        .map(|link| {
+            format!(
+                "SocialLink {{ label: {}, url: {}, icon: {}, rel: {} }}, ",
+                literal(&link.label),
+                literal(&link.url),
+                optional(link.icon.as_deref()),
+                optional(link.rel.as_deref()),
+            )
+        })
+        .collect::<String>();
+    let greeting = site
+        .home
+        .greeting
+        .iter()
+        .map(|line| format!("{}, ", literal(line)))
+        .collect::<String>();
+
+    let constants = [
+        ("AUTHOR", "&str", literal(&site.author.name)),
+        ("BACKGROUND_COLOR", "&str", background),
+        ("BIO", "&str", literal(&site.home.bio)),
+        ("DESCRIPTION", "&str", literal(&site.description)),
+        ("EMAIL", "Option<&str>", optional(site.author.email.as_deref())),
+        ("GITHUB_USERNAME", "&str", literal(&site.author.github)),
+        ("GREETING", "&[&str]", format!("&[{greeting}]")),
+        ("NAVIGATION", "&[NavigationEntry]", format!("&[{navigation}]")),
+        ("PALETTE", "Palette", default_palette.code(&site.theme.palette)),
+        ("PALETTE_STYLE", "&str", literal(&default_palette.style())),
+        ("PALETTES", "&[Palette]", format!("&[{palette_list}]")),
+        ("PRERENDER_MATH", "bool", site.features.prerender_math.to_string()),
+        ("REPOSITORY", "Option<&str>", optional(site.repository.as_deref())),
+        ("ROUTES", "&[&str]", format!("&{ROUTES:?}")),
+        ("SEARCH", "bool", site.features.search.to_string()),
+        ("SELF_HOSTED_PROJECTS", "bool", site.features.self_hosted_projects.to_string()),
+        ("SOCIAL_LINKS", "&[SocialLink]", format!("&[{social}]")),
+        ("SPLASH", "bool", site.splash.enabled.to_string()),
+        ("SPLASH_DURATION", "u32", site.splash.duration.to_string()),
+        ("THEME_COLOR", "&str", primary),
+        ("TITLE", "&str", literal(&site.title)),
+        // paths get appended to it
+        ("URL", "&str", literal(site.url.trim_end_matches('/'))),
+    ];
+
+    let mut code = String::new();
+    for (name, kind, value) in constants {
+        let _ = writeln!(code, "pub const {name}: {kind} = {value};");
+    }
+
+    let output = env::var_os("OUT_DIR").expect("cargo should set OUT_DIR for build scripts");
+    fs::write(Path::new(&output).join("config.rs"), code)
+        .expect("the configuration should be writable");
+}
@@ -44,9 +44,12 @@
                         (craneLib.fileset.commonCargoSources unfilteredRoot)
                         (lib.fileset.fromSource (unfilteredRoot + "/assets"))
                         (lib.fileset.fromSource (unfilteredRoot + "/files"))
+                        (lib.fileset.fromSource (unfilteredRoot + "/generator/src/service-worker.js"))
                         (lib.fileset.fromSource (unfilteredRoot + "/index.html"))
-                        (lib.fileset.fromSource (unfilteredRoot + "/posts.json"))
+                        (lib.fileset.fromSource (unfilteredRoot + "/projects.toml"))
+                        (lib.fileset.fromSource (unfilteredRoot + "/site.toml"))
                         (lib.fileset.fromSource (unfilteredRoot + "/stylesheets"))
+                        (lib.fileset.fromSource (unfilteredRoot + "/Trunk.toml"))
                     ];
                 };
 
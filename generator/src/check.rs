@@ -0,0 +1,190 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use web_segment::diagnostics::{self, Problem, Site};
+
+use crate::post::Post;
+use crate::projects;
+use crate::{FILES_DIRECTORY, PROJECTS_DIRECTORY};
+
+/// The directories copied into the site as they are, by where they're served from.
+const SERVED_DIRECTORIES: [(&str, &str); 2] = [("assets", "/assets"), (FILES_DIRECTORY, "/files")];
+/// The generated files worth linking to from a page.
+const GENERATED_FILES: [&str; 2] = ["/atom.xml", "/feed.xml"];
+
+/// A problem with a page, by the file it's in and the line it's on when it can be told.
+pub struct Report {
+    pub file: String,
+    pub line: Option<usize>,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            None => write!(f, "{}: {}", self.file, self.problem),
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.problem),
+        }
+    }
+}
+
+/// What's wrong with every post and write-up, checked against everything the site serves and
+/// every page's headings.
+pub fn check(posts: &[Post], pages: &[projects::Page]) -> io::Result<Vec<Report>> {
+    let mut files = GENERATED_FILES.map(str::to_owned).into_iter().collect();
+    for (directory, path) in SERVED_DIRECTORIES {
+        list_files(Path::new(directory), path, &mut files)?;
+    }
+
+    Ok(check_against(posts, pages, files))
+}
+
+/// What's wrong with every post and write-up, checked against `files`, the path of every file the
+/// site serves.
+fn check_against(posts: &[Post], pages: &[projects::Page], files: BTreeSet<String>) -> Vec<Report> {
+    let mut site = Site {
+        posts: Some(posts.iter().map(|post| post.filename.clone()).collect()),
+        projects: Some(pages.iter().map(|page| page.name.clone()).collect()),
+        tags: Some(posts.iter().flat_map(|post| post.front_matter.tags.clone()).collect()),
+        files: Some(files),
+        anchors: None,
+    };
+
+    // (file, path it's shown at, source)
+    let posts = posts.iter().map(|post| {
+        (
+            format!("{FILES_DIRECTORY}/{}", post.filename),
+            format!("/post/{}", post.filename),
+            post.source.as_str(),
+        )
+    });
+    let write_ups = pages.iter().map(|page| {
+        (
+            format!("{PROJECTS_DIRECTORY}/{}.md", page.name),
+            format!("/project/{}", page.name),
+            page.body.as_str(),
+        )
+    });
+    let all = posts.chain(write_ups).collect::<Vec<_>>();
+
+    site.anchors = Some(
+        all.iter()
+            .map(|(_, path, source)| (path.clone(), diagnostics::anchors(source)))
+            .collect(),
+    );
+
+    all.iter()
+        .flat_map(|(file, path, source)| {
+            diagnostics::check_page(source, path, &site)
+                .into_iter()
+                .map(|(line, problem)| Report {
+                    file: file.clone(),
+                    line,
+                    problem,
+                })
+        })
+        .collect()
+}
+
+/// Adds every file under `directory` to `files`, by the path it's served from under `path`.
+fn list_files(directory: &Path, path: &str, files: &mut BTreeSet<String>) -> io::Result<()> {
+    let entries = match fs::read_dir(directory) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        entries => entries?,
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let path = format!("{path}/{}", name.to_string_lossy());
+
+        if entry.file_type()?.is_dir() {
+            list_files(&entry.path(), &path, files)?;
+        } else {
+            files.insert(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::post;
+
+    const FIXTURE: &str = r#"+++
+title = "Fixture"
+date = 2024-01-01
++++
+
+# Fixture
+
+[dead](/post/missing.md) and [fine](/post/other.md#usage)
+
+[anchor](/post/other.md#nope)
+
+![missing](/files/missing.png) ![there](/files/image.png)
+"#;
+
+    const OTHER: &str = r#"+++
+title = "Other"
+date = 2024-01-02
++++
+
+## Usage
+"#;
+
+    #[test]
+    fn reports_problems_by_file_and_line() {
+        let directory = env::temp_dir().join(format!("web-segment-check-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("fixture.md"), FIXTURE).unwrap();
+        fs::write(directory.join("other.md"), OTHER).unwrap();
+
+        let posts = post::load_all(&directory);
+        fs::remove_dir_all(&directory).unwrap();
+        let Ok(posts) = posts else {
+            panic!("the fixtures should load");
+        };
+
+        let files = BTreeSet::from(["/files/image.png".to_owned()]);
+        let reports = check_against(&posts, &[], files)
+            .iter()
+            .map(Report::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            reports,
+            [
+                "files/fixture.md:8: dead link to /post/missing.md",
+                concat!(
+                    "files/fixture.md:10: /post/other.md#nope links to a part of the page ",
+                    "that isn't there",
+                ),
+                "files/fixture.md:12: missing image /files/missing.png",
+            ],
+        );
+    }
+}
@@ -0,0 +1,402 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use std::collections::VecDeque;
+
+use pulldown_cmark::{CowStr, Event, Parser, Tag};
+use web_segment::config;
+use web_segment::diagnostics::{self, Element};
+use web_segment::math::{self, Formula};
+
+use crate::post::{self, Post};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    fn of(post: &Post) -> Self {
+        let date = post
+            .front_matter
+            .date
+            .date
+            .expect("post dates should be validated while loading");
+
+        Self {
+            year: date.year,
+            month: date.month,
+            day: date.day,
+        }
+    }
+
+    /// Sakamoto's method, 0 being Sunday.
+    fn weekday(&self) -> usize {
+        const OFFSETS: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+
+        let year = if self.month < 3 { self.year - 1 } else { self.year };
+        let day = year + year / 4 - year / 100 + year / 400
+            + OFFSETS[self.month as usize - 1]
+            + self.day as u16;
+
+        day as usize % 7
+    }
+
+    /// RFC 822, as used by RSS.
+    fn to_rfc822(&self) -> String {
+        format!(
+            "{}, {:02} {} {} 00:00:00 +0000",
+            WEEKDAYS[self.weekday()],
+            self.day,
+            MONTHS[self.month as usize - 1],
+            self.year,
+        )
+    }
+
+    /// RFC 3339, as used by Atom.
+    fn to_rfc3339(&self) -> String {
+        format!("{}-{:02}-{:02}T00:00:00Z", self.year, self.month, self.day)
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+/// Renders a formula the way the app does, minus the styling.
+fn formula(formula: &Formula) -> String {
+    let math = match formula.mathml() {
+        Some(mathml) => mathml.to_owned(),
+        None => format!("<code>{}</code>", escape(formula.latex())),
+    };
+
+    match (formula.display(), formula.number()) {
+        (true, Some(number)) => format!(
+            "<span id=\"{}\">{math} ({})</span>",
+            escape(formula.id().unwrap_or_default()),
+            escape(number),
+        ),
+        _ => math,
+    }
+}
+
+/// The components that wrap markdown of their own, and the plain HTML their closing tags become.
+const CLOSING_TAGS: [(&str, &str); 4] = [
+    ("Callout", "</aside>"),
+    ("Details", "</details>"),
+    ("Tab", "</section>"),
+    ("Tabs", "</div>"),
+];
+
+/// The plain HTML a custom component's opening or self-closing tag becomes, `labels` being what's
+/// left of the labels of the tabs it's in. Anything that isn't a custom component is left alone.
+fn component(
+    element: &Element<'_>,
+    formulas: &[Formula],
+    labels: &mut VecDeque<String>,
+) -> Option<String> {
+    let get = |attribute| element.get(attribute).unwrap_or_default();
+
+    let html = match element.name {
+        "UseTitle" | "UseToc" => String::new(),
+        "UseAnchor" => format!("<a id=\"{}\"></a>", escape(get("id"))),
+        "UseMath" => formula(formulas.get(get("index").parse::<usize>().ok()?)?),
+        "UseLink" => format!(
+            "<a href=\"{}{}\">{}</a>",
+            config::URL,
+            escape(get("link")),
+            escape(get("text")),
+        ),
+        "Callout" => {
+            let kind = element.get("kind").unwrap_or("note");
+            let title = element.get("title").map_or_else(
+                || {
+                    let mut characters = kind.chars();
+                    characters.next().map_or_else(String::new, |first| {
+                        first.to_uppercase().chain(characters).collect()
+                    })
+                },
+                str::to_owned,
+            );
+
+            format!("<aside><p><strong>{}</strong></p>", escape(&title))
+        }
+        "Details" => format!("<details open><summary>{}</summary>", escape(get("summary"))),
+        "Figure" => {
+            let caption = element
+                .get("caption")
+                .map(|caption| format!("<figcaption>{}</figcaption>", escape(caption)))
+                .unwrap_or_default();
+
+            format!(
+                "<figure><img src=\"{}\" alt=\"{}\">{caption}</figure>",
+                escape(&absolute(get("src").into())),
+                escape(get("alt")),
+            )
+        }
+        "Footnote" => format!(" ({})", escape(get("note"))),
+        "Gist" => {
+            // the ID is what comes last in the gist's URL, which is also accepted whole
+            let id = get("id").trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+            let text = element.get("file").unwrap_or("the gist");
+
+            format!(
+                "<p><a href=\"https://gist.github.com/{}\">{}</a></p>",
+                escape(id),
+                escape(text),
+            )
+        }
+        "Include" => format!(
+            "<p><a href=\"{}\">{}</a></p>",
+            escape(&absolute(get("path").into())),
+            escape(get("path")),
+        ),
+        "Tab" => format!(
+            "<section><h4>{}</h4>",
+            escape(&labels.pop_front().unwrap_or_default()),
+        ),
+        "Tabs" => {
+            *labels = get("labels").split(',').map(|label| label.trim().to_owned()).collect();
+            "<div>".to_owned()
+        }
+        "Video" => format!(
+            "<p><a href=\"{}\">{}</a></p>",
+            escape(&absolute(get("src").into())),
+            escape(get("title")),
+        ),
+        "YouTube" => {
+            let start = element
+                .get("start")
+                .map(|start| format!("&t={start}s"))
+                .unwrap_or_default();
+
+            format!(
+                "<p><a href=\"https://www.youtube.com/watch?v={}{}\">{}</a></p>",
+                escape(get("id")),
+                escape(&start),
+                escape(get("title")),
+            )
+        }
+        _ => return None,
+    };
+
+    Some(html)
+}
+
+/// Swaps custom components for plain HTML, as feed readers only know the latter.
+fn replace_components(html: &str, formulas: &[Formula], labels: &mut VecDeque<String>) -> String {
+    let close = |mut text: String| {
+        for (name, replacement) in CLOSING_TAGS {
+            text = text.replace(&format!("</{name}>"), replacement);
+        }
+        text
+    };
+
+    let mut replaced = String::with_capacity(html.len());
+    let mut last = 0;
+
+    for element in diagnostics::elements(html) {
+        let tag = &html[element.range.clone()];
+
+        replaced.push_str(&close(html[last..element.range.start].to_owned()));
+        replaced.push_str(&component(&element, formulas, labels).unwrap_or_else(|| tag.to_owned()));
+        last = element.range.end;
+    }
+    replaced.push_str(&close(html[last..].to_owned()));
+
+    replaced
+}
+
+/// Prefixes root-relative URLs with the site's, since RSS has no way to declare a base URL.
+fn absolute(url: CowStr<'_>) -> CowStr<'_> {
+    match url.starts_with('/') && !url.starts_with("//") {
+        true => format!("{}{}", config::URL, url).into(),
+        false => url,
+    }
+}
+
+fn render(post: &Post) -> String {
+    // formulas are rendered like the app does, rather than left as LaTeX for readers to make out
+    let (body, formulas) = math::extract_formulas(&post.body, &math::macros(&post.source), &[]);
+
+    // tabs are opened and their panels are closed in separate chunks of HTML
+    let mut labels = VecDeque::new();
+
+    let events = Parser::new_ext(&body, post::MARKDOWN_OPTIONS).map(|event| match event {
+        Event::Html(html) => Event::Html(replace_components(&html, &formulas, &mut labels).into()),
+        Event::InlineHtml(html) => {
+            Event::InlineHtml(replace_components(&html, &formulas, &mut labels).into())
+        }
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: absolute(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: absolute(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+fn url(post: &Post) -> String {
+    format!("{}/post/{}", config::URL, post.filename)
+}
+
+fn rss(posts: &[Post]) -> String {
+    let mut items = String::new();
+
+    for post in posts {
+        let _ = write!(
+            items,
+            "<item>\
+                <title>{title}</title>\
+                <link>{url}</link>\
+                <guid>{url}</guid>\
+                <pubDate>{date}</pubDate>\
+                {categories}\
+                <description>{content}</description>\
+            </item>",
+            title = escape(&post.front_matter.title),
+            url = escape(&url(post)),
+            date = Date::of(post).to_rfc822(),
+            categories = post
+                .front_matter
+                .tags
+                .iter()
+                .map(|tag| format!("<category>{}</category>", escape(tag)))
+                .collect::<String>(),
+            content = escape(&render(post)),
+        );
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+        <rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\
+            <channel>\
+                <title>{title}</title>\
+                <link>{url}/</link>\
+                <description>{description}</description>\
+                <atom:link href=\"{url}/feed.xml\" rel=\"self\" type=\"application/rss+xml\"/>\
+                {items}\
+            </channel>\
+        </rss>",
+        title = escape(config::TITLE),
+        url = escape(config::URL),
+        description = escape(config::DESCRIPTION),
+    )
+}
+
+fn atom(posts: &[Post]) -> String {
+    let mut entries = String::new();
+
+    for post in posts {
+        let _ = write!(
+            entries,
+            "<entry>\
+                <title>{title}</title>\
+                <link href=\"{url}\"/>\
+                <id>{url}</id>\
+                <updated>{date}</updated>\
+                {categories}\
+                <content type=\"html\">{content}</content>\
+            </entry>",
+            title = escape(&post.front_matter.title),
+            url = escape(&url(post)),
+            date = Date::of(post).to_rfc3339(),
+            categories = post
+                .front_matter
+                .tags
+                .iter()
+                .map(|tag| format!("<category term=\"{}\"/>", escape(tag)))
+                .collect::<String>(),
+            content = escape(&render(post)),
+        );
+    }
+
+    // posts are sorted newest first
+    let updated = posts
+        .first()
+        .map_or_else(|| "1970-01-01T00:00:00Z".to_owned(), |post| Date::of(post).to_rfc3339());
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+        <feed xmlns=\"http://www.w3.org/2005/Atom\" xml:base=\"{url}/\">\
+            <title>{title}</title>\
+            <subtitle>{description}</subtitle>\
+            <link href=\"{url}/\"/>\
+            <link href=\"{url}/atom.xml\" rel=\"self\"/>\
+            <id>{url}/</id>\
+            <updated>{updated}</updated>\
+            <author><name>{author}</name>{email}</author>\
+            {entries}\
+        </feed>",
+        url = escape(config::URL),
+        title = escape(config::TITLE),
+        description = escape(config::DESCRIPTION),
+        author = escape(config::AUTHOR),
+        email = config::EMAIL
+            .map(|email| format!("<email>{}</email>", escape(email)))
+            .unwrap_or_default(),
+    )
+}
+
+pub fn write(posts: &[Post], directory: &Path) -> io::Result<()> {
+    fs::write(directory.join("feed.xml"), rss(posts))?;
+    fs::write(directory.join("atom.xml"), atom(posts))
+}
@@ -0,0 +1,43 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::post::{self, Post};
+use crate::projects;
+
+/// The code blocks of the markdown file `source` highlighted, which the app can't do itself.
+pub fn json(source: &str) -> String {
+    serde_json::to_string(&web_segment::highlight::prerender(source))
+        .expect("the highlighted code should be serializable")
+}
+
+/// Writes the highlighted code blocks of every post and write-up to where the app looks for them.
+pub fn write(posts: &[Post], pages: &[projects::Page], directory: &Path) -> io::Result<()> {
+    for (file, source) in post::files(posts, pages) {
+        let path = directory.join(web_segment::highlight::path(&file).trim_start_matches('/'));
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, json(source))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::post::Post;
+
+/// Mirrors `index::Post` in the web app.
+#[derive(Serialize)]
+struct Entry<'a> {
+    title: &'a str,
+    date: String,
+    filename: &'a str,
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<&'a str>,
+}
+
+pub fn json(posts: &[Post]) -> String {
+    let entries = posts
+        .iter()
+        .map(|post| Entry {
+            title: &post.front_matter.title,
+            date: post.front_matter.date.to_string(),
+            filename: &post.filename,
+            tags: &post.front_matter.tags,
+            description: post.front_matter.description.as_deref(),
+            image: post.front_matter.image.as_deref(),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&entries).expect("the post index should be serializable")
+}
+
+pub fn write(posts: &[Post], directory: &Path) -> io::Result<()> {
+    fs::write(directory.join("posts.json"), json(posts))
+}
@@ -0,0 +1,183 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+mod check;
+mod feed;
+mod highlight;
+mod index;
+mod manifest;
+mod math;
+mod palette;
+mod post;
+mod prerender;
+mod projects;
+mod search;
+mod service_worker;
+
+const FILES_DIRECTORY: &str = "files";
+const PROJECTS_DIRECTORY: &str = "files/projects";
+const PROJECTS_FILE: &str = "projects.toml";
+
+/// Run without a command before trunk builds the app, checking every page for problems and writing
+/// the post index, search index, feeds, project list, highlighted code, prerendered formulas,
+/// palette stylesheet and web app manifest, then with `prerender` after the build, once there's an
+/// `index.html` to render into and a bundle for the service worker to precache.
+///
+/// Run with `check` to only check the pages, e.g. while writing them.
+fn main() -> ExitCode {
+    let mut arguments = env::args_os().skip(1).peekable();
+    let prerender = arguments.next_if(|argument| argument == "prerender").is_some();
+    let check_only = !prerender && arguments.next_if(|argument| argument == "check").is_some();
+
+    // when run as a trunk hook, the output goes straight into the staging directory
+    let output_directory = arguments
+        .next()
+        .or_else(|| env::var_os("TRUNK_STAGING_DIR"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let posts = match post::load_all(Path::new(FILES_DIRECTORY)) {
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+        Ok(posts) => posts,
+    };
+
+    let projects = match projects::load(Path::new(PROJECTS_FILE)) {
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+        Ok(projects) => projects,
+    };
+
+    let project_pages = match projects::load_pages(Path::new(PROJECTS_DIRECTORY)) {
+        Err(error) => {
+            eprintln!("error: {error}");
+            return ExitCode::FAILURE;
+        }
+        Ok(pages) => pages,
+    };
+
+    if check_only {
+        if !check(&posts, &project_pages) {
+            return ExitCode::FAILURE;
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    if prerender {
+        if let Err(error) =
+            prerender::write(&posts, projects.as_deref(), &project_pages, &output_directory)
+        {
+            eprintln!("error: couldn't prerender the pages: {error}");
+            return ExitCode::FAILURE;
+        }
+
+        if let Err(error) = service_worker::write(&output_directory) {
+            eprintln!("error: couldn't write the service worker: {error}");
+            return ExitCode::FAILURE;
+        }
+
+        return ExitCode::SUCCESS;
+    }
+
+    // so broken links and components never ship
+    if !check(&posts, &project_pages) {
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(error) = index::write(&posts, &output_directory) {
+        eprintln!("error: couldn't write the post index: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    if web_segment::config::SEARCH {
+        if let Err(error) = search::write(&posts, &output_directory) {
+            eprintln!("error: couldn't write the search index: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Err(error) = feed::write(&posts, &output_directory) {
+        eprintln!("error: couldn't write the feeds: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(projects) = &projects {
+        if let Err(error) = projects::write(projects, &output_directory) {
+            eprintln!("error: couldn't write the project list: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Err(error) = projects::write_pages(&project_pages, &output_directory) {
+        eprintln!("error: couldn't write the project page list: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(error) = highlight::write(&posts, &project_pages, &output_directory) {
+        eprintln!("error: couldn't write the highlighted code: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    if web_segment::config::PRERENDER_MATH {
+        if let Err(error) = math::write(&posts, &project_pages, &output_directory) {
+            eprintln!("error: couldn't write the prerendered formulas: {error}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if let Err(error) = palette::write(&output_directory) {
+        eprintln!("error: couldn't write the palette stylesheet: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(error) = manifest::write(&output_directory) {
+        eprintln!("error: couldn't write the web app manifest: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Checks every post and write-up, printing what's wrong with them, and returns whether nothing
+/// was.
+fn check(posts: &[post::Post], pages: &[projects::Page]) -> bool {
+    let reports = match check::check(posts, pages) {
+        Err(error) => {
+            eprintln!("error: couldn't check the pages: {error}");
+            return false;
+        }
+        Ok(reports) => reports,
+    };
+
+    for report in &reports {
+        eprintln!("error: {report}");
+    }
+
+    if !reports.is_empty() {
+        eprintln!("error: found {} problem(s) in the pages", reports.len());
+    }
+
+    reports.is_empty()
+}
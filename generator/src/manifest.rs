@@ -0,0 +1,66 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+use web_segment::config;
+
+#[derive(Serialize)]
+struct Icon {
+    src: &'static str,
+    sizes: &'static str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct Manifest {
+    name: &'static str,
+    short_name: &'static str,
+    description: &'static str,
+    start_url: &'static str,
+    scope: &'static str,
+    display: &'static str,
+    background_color: &'static str,
+    theme_color: &'static str,
+    icons: [Icon; 1],
+}
+
+pub fn write(directory: &Path) -> io::Result<()> {
+    let manifest = Manifest {
+        name: config::TITLE,
+        short_name: config::TITLE,
+        description: config::DESCRIPTION,
+        start_url: "/",
+        scope: "/",
+        display: "standalone",
+        background_color: config::BACKGROUND_COLOR,
+        theme_color: config::THEME_COLOR,
+        icons: [Icon {
+            src: "/assets/favicon.ico",
+            sizes: "16x16 32x32 48x48",
+            kind: "image/x-icon",
+        }],
+    };
+
+    fs::write(
+        directory.join("manifest.webmanifest"),
+        serde_json::to_string_pretty(&manifest).expect("the manifest should be serializable"),
+    )
+}
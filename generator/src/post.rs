@@ -0,0 +1,153 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use pulldown_cmark::{Options, Parser};
+use serde::Deserialize;
+use web_segment::utils::{self, FRONT_MATTER_DELIMITER};
+
+use crate::projects;
+
+pub const MARKDOWN_OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_FOOTNOTES)
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_TASKLISTS)
+    .union(Options::ENABLE_MATH);
+
+#[derive(Deserialize)]
+pub struct FrontMatter {
+    pub title: String,
+    pub date: toml::value::Datetime,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// A short summary used by link previews and search engines.
+    pub description: Option<String>,
+    /// A path or URL to the image shown in link previews.
+    pub image: Option<String>,
+}
+
+pub struct Post {
+    pub body: String,
+    /// The whole file, front matter included, as the app fetches it.
+    pub source: String,
+    pub filename: String,
+    pub front_matter: FrontMatter,
+}
+
+pub enum Error {
+    InvalidDate(PathBuf),
+    InvalidFrontMatter(PathBuf, toml::de::Error),
+    Io(PathBuf, io::Error),
+    MissingFrontMatter(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidDate(path) => {
+                write!(f, "{}: invalid front matter: `date` must be a calendar date", path.display())
+            }
+            Self::InvalidFrontMatter(path, error) => {
+                write!(f, "{}: invalid front matter: {}", path.display(), error.message())
+            }
+            Self::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            Self::MissingFrontMatter(path) => write!(
+                f,
+                "{}: missing front matter, expected the post to start with a `{}` delimited block",
+                path.display(),
+                FRONT_MATTER_DELIMITER,
+            ),
+        }
+    }
+}
+
+impl Post {
+    fn load(path: &Path) -> Result<Self, Error> {
+        let source = fs::read_to_string(path).map_err(|error| Error::Io(path.to_owned(), error))?;
+
+        let Some((front_matter, body)) = utils::split_front_matter(&source) else {
+            return Err(Error::MissingFrontMatter(path.to_owned()));
+        };
+
+        let front_matter: FrontMatter = toml::from_str(front_matter)
+            .map_err(|error| Error::InvalidFrontMatter(path.to_owned(), error))?;
+
+        if front_matter.date.date.is_none() {
+            return Err(Error::InvalidDate(path.to_owned()));
+        }
+
+        Ok(Self {
+            body: body.to_owned(),
+            filename: path
+                .file_name()
+                .expect("path should have a file name as it was read from a directory")
+                .to_string_lossy()
+                .into_owned(),
+            front_matter,
+            source,
+        })
+    }
+
+    pub fn parser(&self) -> Parser<'_> {
+        Parser::new_ext(&self.body, MARKDOWN_OPTIONS)
+    }
+}
+
+/// Loads every markdown file directly inside `directory`, newest first.
+pub fn load_all(directory: &Path) -> Result<Vec<Post>, Error> {
+    let entries = fs::read_dir(directory).map_err(|error| Error::Io(directory.to_owned(), error))?;
+
+    let mut posts = Vec::new();
+
+    for entry in entries {
+        let path = entry
+            .map_err(|error| Error::Io(directory.to_owned(), error))?
+            .path();
+
+        if !path.is_file() || path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+
+        posts.push(Post::load(&path)?);
+    }
+
+    posts.sort_by(|a, b| {
+        b.front_matter
+            .date
+            .to_string()
+            .cmp(&a.front_matter.date.to_string())
+            .then_with(|| a.filename.cmp(&b.filename))
+    });
+
+    Ok(posts)
+}
+
+/// Every post and write-up, by the path the app fetches them from.
+pub fn files<'a>(
+    posts: &'a [Post],
+    pages: &'a [projects::Page],
+) -> impl Iterator<Item = (String, &'a str)> {
+    let posts = posts
+        .iter()
+        .map(|post| (format!("/files/{}", post.filename), post.source.as_str()));
+    let pages = pages
+        .iter()
+        .map(|page| (format!("/files/projects/{}.md", page.name), page.body.as_str()));
+
+    posts.chain(pages)
+}
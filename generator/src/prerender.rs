@@ -0,0 +1,141 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use web_segment::{config, Page, Route};
+
+use crate::highlight;
+use crate::index;
+use crate::math;
+use crate::post::Post;
+use crate::projects::{self, Project};
+
+fn pages(
+    posts: &[Post],
+    projects: Option<&[Project]>,
+    project_pages: &[projects::Page],
+) -> Vec<Page> {
+    let index = index::json(posts);
+    let with_index = || HashMap::from([("/posts.json".to_owned(), index.clone())]);
+
+    // otherwise the app fetches the projects from GitHub, and would never read these
+    let projects = projects
+        .filter(|_| config::SELF_HOSTED_PROJECTS)
+        .map(|projects| ("/projects.json".to_owned(), projects::json(projects)));
+    let with_projects = || projects.clone().into_iter().collect::<HashMap<_, _>>();
+
+    // a page along with its highlighting, and its formulas if the app reads them rather than
+    // rendering them itself
+    let with_source = |file: &str, source: &str| {
+        let mut responses = HashMap::from([
+            (file.to_owned(), source.to_owned()),
+            (web_segment::highlight::path(file), highlight::json(source)),
+        ]);
+        if config::PRERENDER_MATH {
+            responses.insert(web_segment::math::path(file), math::json(source));
+        }
+        responses
+    };
+
+    let mut pages = vec![
+        Page {
+            route: Route::Home,
+            responses: HashMap::new(),
+        },
+        Page {
+            route: Route::NotFound,
+            responses: HashMap::new(),
+        },
+        Page {
+            route: Route::Posts,
+            responses: with_index(),
+        },
+        Page {
+            route: Route::Projects,
+            responses: {
+                let mut responses = with_projects();
+                responses.insert(
+                    "/project-pages.json".to_owned(),
+                    projects::pages_json(project_pages),
+                );
+                responses
+            },
+        },
+    ];
+
+    pages.extend(project_pages.iter().map(|page| Page {
+        route: Route::Project {
+            name: page.name.clone(),
+        },
+        responses: {
+            let mut responses = with_projects();
+            responses.extend(with_source(
+                &format!("/files/projects/{}.md", page.name),
+                &page.body,
+            ));
+            responses
+        },
+    }));
+
+    pages.extend(posts.iter().map(|post| Page {
+        route: Route::Post {
+            filename: post.filename.clone(),
+        },
+        responses: {
+            let mut responses = with_index();
+            responses.extend(with_source(&format!("/files/{}", post.filename), &post.source));
+            responses
+        },
+    }));
+
+    let tags = posts
+        .iter()
+        .flat_map(|post| post.front_matter.tags.iter())
+        .collect::<BTreeSet<_>>();
+
+    pages.extend(tags.into_iter().map(|tag| Page {
+        route: Route::Tag { tag: tag.clone() },
+        responses: with_index(),
+    }));
+
+    pages
+}
+
+/// Renders every route into its own copy of the `index.html` trunk left in `directory`.
+pub fn write(
+    posts: &[Post],
+    projects: Option<&[Project]>,
+    project_pages: &[projects::Page],
+    directory: &Path,
+) -> io::Result<()> {
+    let template = fs::read_to_string(directory.join("index.html"))?;
+
+    for page in pages(posts, projects, project_pages) {
+        let path = directory.join(page.file_path());
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, page.render(&template))?;
+    }
+
+    Ok(())
+}
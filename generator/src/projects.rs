@@ -0,0 +1,177 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use serde::{Deserialize, Serialize, Serializer};
+use toml::value::Datetime;
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    projects: Vec<Project>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Link {
+    pub title: String,
+    pub url: String,
+}
+
+/// Mirrors `Project` in the web app's projects page.
+#[derive(Deserialize, Serialize)]
+pub struct Project {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The primary language, e.g. `Rust`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// A CSS colour for the language, GitHub's being used for common ones without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stars: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forks: Option<u64>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_date"
+    )]
+    pub updated: Option<Datetime>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The first link is the one the project's card leads to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Link>,
+    /// Paths or URLs to images of the project, the first of which is shown on its card.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<String>,
+    /// Featured projects are listed first.
+    #[serde(default)]
+    pub featured: bool,
+}
+
+/// Dates would otherwise be serialized the way the `toml` crate represents them internally.
+fn serialize_date<S: Serializer>(date: &Option<Datetime>, serializer: S) -> Result<S::Ok, S::Error> {
+    date.as_ref().map(Datetime::to_string).serialize(serializer)
+}
+
+/// A write-up about a project, shown on its own page instead of the card linking away.
+pub struct Page {
+    pub body: String,
+    /// The name of the project, which is also the file's name without the `.md` extension.
+    pub name: String,
+}
+
+pub enum Error {
+    Invalid(PathBuf, toml::de::Error),
+    InvalidDate(PathBuf, String),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(path, error) => write!(f, "{}: {}", path.display(), error.message()),
+            Self::InvalidDate(path, name) => write!(
+                f,
+                "{}: `updated` of project `{}` must be a calendar date",
+                path.display(),
+                name,
+            ),
+            Self::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+/// Reads the self-hosted project list, which is optional since the projects can come from
+/// GitHub instead.
+pub fn load(path: &Path) -> Result<Option<Vec<Project>>, Error> {
+    let source = match fs::read_to_string(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(Error::Io(path.to_owned(), error)),
+        Ok(source) => source,
+    };
+
+    let file: File =
+        toml::from_str(&source).map_err(|error| Error::Invalid(path.to_owned(), error))?;
+
+    // the web app shows the date as is
+    if let Some(project) = file.projects.iter().find(|project| {
+        project
+            .updated
+            .is_some_and(|updated| updated.date.is_none() || updated.time.is_some())
+    }) {
+        return Err(Error::InvalidDate(path.to_owned(), project.name.clone()));
+    }
+
+    Ok(Some(file.projects))
+}
+
+/// Reads the write-ups in `directory`, which doesn't have to exist.
+pub fn load_pages(directory: &Path) -> Result<Vec<Page>, Error> {
+    let entries = match fs::read_dir(directory) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(Error::Io(directory.to_owned(), error)),
+        Ok(entries) => entries,
+    };
+
+    let mut pages = Vec::new();
+
+    for entry in entries {
+        let path = entry
+            .map_err(|error| Error::Io(directory.to_owned(), error))?
+            .path();
+
+        if !path.is_file() || path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        pages.push(Page {
+            body: fs::read_to_string(&path).map_err(|error| Error::Io(path.clone(), error))?,
+            name: name.to_owned(),
+        });
+    }
+
+    pages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(pages)
+}
+
+pub fn json(projects: &[Project]) -> String {
+    serde_json::to_string(projects).expect("the project list should be serializable")
+}
+
+pub fn write(projects: &[Project], directory: &Path) -> io::Result<()> {
+    fs::write(directory.join("projects.json"), json(projects))
+}
+
+/// Lists the projects with a write-up, whichever source the projects themselves come from.
+pub fn pages_json(pages: &[Page]) -> String {
+    let names = pages.iter().map(|page| &page.name).collect::<Vec<_>>();
+
+    serde_json::to_string(&names).expect("the project page list should be serializable")
+}
+
+pub fn write_pages(pages: &[Page], directory: &Path) -> io::Result<()> {
+    fs::write(directory.join("project-pages.json"), pages_json(pages))
+}
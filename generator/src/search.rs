@@ -0,0 +1,125 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use pulldown_cmark::{Event, TagEnd};
+use serde::Serialize;
+use web_segment::search::tokenize;
+
+use crate::post::Post;
+
+/// How many characters of each post's text are kept for result snippets.
+const EXCERPT_LENGTH: usize = 400;
+const TAG_WEIGHT: u32 = 3;
+const TITLE_WEIGHT: u32 = 5;
+
+/// Mirrors `search::Document` in the web app.
+#[derive(Serialize)]
+struct Document<'a> {
+    title: &'a str,
+    date: String,
+    filename: &'a str,
+    tags: &'a [String],
+    excerpt: String,
+}
+
+/// Mirrors `search::SearchIndex` in the web app, postings being `(document, weight)` pairs.
+#[derive(Serialize)]
+struct SearchIndex<'a> {
+    documents: Vec<Document<'a>>,
+    terms: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+/// Flattens a post into the text a reader would see, leaving out raw HTML, custom components and
+/// math.
+fn plain_text(post: &Post) -> String {
+    let mut text = String::with_capacity(post.body.len());
+
+    for event in post.parser() {
+        match event {
+            Event::Text(content) | Event::Code(content) => text.push_str(&content),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::Item
+                | TagEnd::TableCell
+                | TagEnd::BlockQuote(_),
+            ) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn write(posts: &[Post], directory: &Path) -> io::Result<()> {
+    let mut terms = BTreeMap::<String, BTreeMap<usize, u32>>::new();
+
+    let documents = posts
+        .iter()
+        .enumerate()
+        .map(|(index, post)| {
+            let text = plain_text(post);
+
+            let mut add = |text: &str, weight: u32| {
+                for term in tokenize(text) {
+                    *terms.entry(term).or_default().entry(index).or_default() += weight;
+                }
+            };
+
+            add(&post.front_matter.title, TITLE_WEIGHT);
+            for tag in &post.front_matter.tags {
+                add(tag, TAG_WEIGHT);
+            }
+            add(&text, 1);
+
+            let excerpt = match text.char_indices().nth(EXCERPT_LENGTH) {
+                None => text,
+                Some((end, _)) => {
+                    // cut at a word boundary so the snippet doesn't end mid-word
+                    let end = text[..end].rfind(' ').unwrap_or(end);
+                    format!("{}…", &text[..end])
+                }
+            };
+
+            Document {
+                title: &post.front_matter.title,
+                date: post.front_matter.date.to_string(),
+                filename: &post.filename,
+                tags: &post.front_matter.tags,
+                excerpt,
+            }
+        })
+        .collect();
+
+    let index = SearchIndex {
+        documents,
+        terms: terms
+            .into_iter()
+            .map(|(term, postings)| (term, postings.into_iter().collect()))
+            .collect(),
+    };
+
+    let json = serde_json::to_string(&index)?;
+
+    fs::write(directory.join("search.json"), json)
+}
@@ -0,0 +1,116 @@
+// Generated by web-segment-generator after every build, which fills in the version and the list
+// of files to precache.
+
+const VERSION = "{{VERSION}}";
+const PRECACHE = {{PRECACHE}};
+
+const PRECACHE_NAME = `precache-${VERSION}`;
+const RUNTIME_NAME = "runtime";
+
+// Posted to the page whenever content is answered from the cache because the network failed, and
+// whenever the network answers again.
+const SERVED_FROM_CACHE = "served-from-cache";
+const SERVED_FROM_NETWORK = "served-from-network";
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(
+        caches
+            .open(PRECACHE_NAME)
+            .then((cache) => cache.addAll(PRECACHE))
+            .then(() => self.skipWaiting()),
+    );
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches
+            .keys()
+            .then((names) =>
+                Promise.all(
+                    names
+                        .filter((name) => name !== PRECACHE_NAME && name !== RUNTIME_NAME)
+                        .map((name) => caches.delete(name)),
+                ),
+            )
+            .then(() => self.clients.claim()),
+    );
+});
+
+self.addEventListener("fetch", (event) => {
+    const url = new URL(event.request.url);
+
+    if (event.request.method !== "GET" || url.origin !== self.location.origin) {
+        return;
+    }
+
+    if (event.request.mode === "navigate") {
+        event.respondWith(networkFirst(event));
+    } else if (isContent(url)) {
+        event.respondWith(staleWhileRevalidate(event));
+    } else if (PRECACHE.includes(url.pathname)) {
+        event.respondWith(caches.match(url.pathname).then((cached) => cached ?? fetch(event.request)));
+    }
+});
+
+function isContent(url) {
+    return (
+        url.pathname === "/posts.json" ||
+        url.pathname === "/project-pages.json" ||
+        url.pathname === "/projects.json" ||
+        url.pathname === "/search.json" ||
+        (url.pathname.startsWith("/files/") && url.pathname.endsWith(".md")) ||
+        (url.pathname.startsWith("/code/") && url.pathname.endsWith(".json")) ||
+        (url.pathname.startsWith("/math/") && url.pathname.endsWith(".json"))
+    );
+}
+
+async function notify(clientId, message) {
+    const client = clientId && (await self.clients.get(clientId));
+
+    if (client) {
+        client.postMessage(message);
+    }
+}
+
+// Pages are prerendered at build time, so the network is preferred, falling back to the last copy
+// seen and then to the home page, which the app renders any route from when it doesn't match.
+async function networkFirst(event) {
+    const cache = await caches.open(RUNTIME_NAME);
+
+    try {
+        const response = await fetch(event.request);
+
+        if (response.ok) {
+            await cache.put(event.request, response.clone());
+        }
+
+        return response;
+    } catch (error) {
+        const cached = (await cache.match(event.request)) ?? (await caches.match("/index.html"));
+        return cached ?? Response.error();
+    }
+}
+
+// The URL alone is used as the key, as the app revalidates with conditional requests whose `304`
+// responses can't be cached.
+async function staleWhileRevalidate(event) {
+    const url = event.request.url;
+    const cache = await caches.open(RUNTIME_NAME);
+    const cached = await cache.match(url);
+
+    const revalidation = fetch(url).then(async (response) => {
+        if (response.ok) {
+            await cache.put(url, response.clone());
+        }
+
+        await notify(event.clientId, SERVED_FROM_NETWORK);
+        return response;
+    });
+
+    if (!cached) {
+        return revalidation;
+    }
+
+    event.waitUntil(revalidation.catch(() => notify(event.clientId, SERVED_FROM_CACHE)));
+    return cached;
+}
@@ -0,0 +1,77 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::Hasher;
+use std::io;
+use std::path::Path;
+
+const FILENAME: &str = "service-worker.js";
+const TEMPLATE: &str = include_str!("service-worker.js");
+
+/// Whether the service worker should fetch `path` ahead of time, content being left out as it's
+/// cached as it gets read instead.
+fn precached(path: &str) -> bool {
+    match path {
+        "/index.html" | "/manifest.webmanifest" => true,
+        _ => {
+            !path.starts_with("/files/")
+                && !path.ends_with(".html")
+                && !path.ends_with(".json")
+                && !path.ends_with(".xml")
+                && path != format!("/{FILENAME}")
+        }
+    }
+}
+
+fn collect(directory: &Path, prefix: &str, paths: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = format!("{prefix}/{}", entry.file_name().to_string_lossy());
+
+        if entry.file_type()?.is_dir() {
+            collect(&entry.path(), &path, paths)?;
+        } else if precached(&path) {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a service worker precaching the app that trunk built into `directory`, versioned by the
+/// contents of what it precaches so browsers pick up new builds.
+pub fn write(directory: &Path) -> io::Result<()> {
+    let mut paths = Vec::new();
+    collect(directory, "", &mut paths)?;
+    paths.sort();
+
+    let mut hasher = DefaultHasher::new();
+    for path in &paths {
+        hasher.write(path.as_bytes());
+        hasher.write(&fs::read(directory.join(path.trim_start_matches('/')))?);
+    }
+
+    let service_worker = TEMPLATE
+        .replace("{{VERSION}}", &format!("{:016x}", hasher.finish()))
+        .replace(
+            "{{PRECACHE}}",
+            &serde_json::to_string(&paths).expect("paths should be serializable"),
+        );
+
+    fs::write(directory.join(FILENAME), service_worker)
+}
@@ -0,0 +1,108 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use reqwest::header::{HeaderMap, HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+
+struct Entry {
+    body: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Responses fetched so far, keyed by the URL they were requested with, shared as a context so
+/// pages render straight away when navigated back to.
+#[derive(Clone, Default)]
+pub struct Cache {
+    entries: Rc<RefCell<HashMap<String, Entry>>>,
+}
+
+impl PartialEq for Cache {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.entries, &other.entries)
+    }
+}
+
+impl Cache {
+    /// Looks up the last successful response for `url`, without revalidating it.
+    pub fn get(&self, url: &str) -> Option<String> {
+        self.entries
+            .borrow()
+            .get(url)
+            .map(|entry| entry.body.clone())
+    }
+
+    /// Fetches `url`, a path being resolved against the origin of the site, revalidating the
+    /// cached response rather than downloading it again when the server supports it.
+    pub async fn fetch(&self, url: &str) -> reqwest::Result<String> {
+        let (etag, last_modified) = match self.entries.borrow().get(url) {
+            None => (None, None),
+            Some(entry) => (entry.etag.clone(), entry.last_modified.clone()),
+        };
+
+        let absolute = match url.starts_with('/') {
+            false => url.to_owned(),
+            true => {
+                let base = web_sys::window().unwrap().location().origin().unwrap();
+                format!("{base}{url}")
+            }
+        };
+
+        let mut request = reqwest::Client::new().get(absolute);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(body) = self.get(url) {
+                return Ok(body);
+            }
+        }
+
+        let response = response.error_for_status()?;
+        let etag = header(response.headers(), ETAG);
+        let last_modified = header(response.headers(), LAST_MODIFIED);
+
+        let body = response.text().await?;
+
+        self.entries.borrow_mut().insert(
+            url.to_owned(),
+            Entry {
+                body: body.clone(),
+                etag,
+                last_modified,
+            },
+        );
+
+        Ok(body)
+    }
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
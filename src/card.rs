@@ -15,6 +15,14 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::utils;
+use crate::Route;
+
+#[derive(Clone, PartialEq)]
+pub struct Language {
+    pub name: String,
+    /// A CSS colour for the dot next to the name, the dot being left out without one.
+    pub color: Option<String>,
+}
 
 #[derive(Clone, PartialEq, yew::Properties)]
 pub struct Props {
@@ -23,19 +31,46 @@ pub struct Props {
     #[prop_or_default]
     pub subtext: Option<String>,
     #[prop_or_default]
+    pub description: Option<String>,
+    #[prop_or_default]
+    pub language: Option<Language>,
+    #[prop_or_default]
+    pub stars: Option<String>,
+    #[prop_or_default]
+    pub forks: Option<String>,
+    /// When the card's subject last changed, e.g. a repository's last commit.
+    #[prop_or_default]
+    pub updated: Option<String>,
+    #[prop_or_default]
     pub image_url: Option<String>,
+    #[prop_or_default]
+    pub tags: Vec<String>,
+    #[prop_or_default]
+    pub children: yew::Html,
 }
 
 #[yew::function_component(Card)]
 pub fn card(properties: &Props) -> yew::Html {
-    let location = web_sys::window()
-        .unwrap()
-        .document()
-        .unwrap()
-        .location()
-        .unwrap();
     let navigator = yew_router::hooks::use_navigator().unwrap();
 
+    let tags = properties.tags.iter().map(|tag| {
+        let navigator = navigator.clone();
+        let route = Route::Tag { tag: tag.clone() };
+
+        // the card itself is clickable, so the click mustn't reach it
+        let onclick = move |event: yew::MouseEvent| {
+            event.stop_propagation();
+            navigator.push(&route);
+        };
+
+        yew::html!( <small onclick={onclick} class={yew::classes!("tag")}>{format!("#{tag}")}</small> )
+    });
+
+    let has_meta = properties.language.is_some()
+        || properties.stars.is_some()
+        || properties.forks.is_some()
+        || properties.updated.is_some();
+
     let inner = yew::html! {
         <>
             <div class={yew::classes!("card-head")}>
@@ -43,8 +78,41 @@ pub fn card(properties: &Props) -> yew::Html {
                 if let Some(subtext) = &properties.subtext {
                     <small class={yew::classes!("card-subtext")}>{subtext.clone()}</small>
                 }
+                if !properties.tags.is_empty() {
+                    <div class={yew::classes!("card-tags")}>
+                        { for tags }
+                    </div>
+                }
             </div>
 
+            if let Some(description) = &properties.description {
+                <p class={yew::classes!("card-description")}>{description.clone()}</p>
+            }
+
+            if has_meta {
+                <div class={yew::classes!("card-meta")}>
+                    if let Some(language) = &properties.language {
+                        <span class={yew::classes!("card-language")}>
+                            if let Some(color) = &language.color {
+                                <span class={yew::classes!("card-language-dot")} style={format!("background-color: {color}")}></span>
+                            }
+                            {language.name.clone()}
+                        </span>
+                    }
+                    if let Some(stars) = &properties.stars {
+                        <span title="Stars">{format!("★ {stars}")}</span>
+                    }
+                    if let Some(forks) = &properties.forks {
+                        <span title="Forks">{format!("⑂ {forks}")}</span>
+                    }
+                    if let Some(updated) = &properties.updated {
+                        <span>{format!("Updated {updated}")}</span>
+                    }
+                </div>
+            }
+
+            { properties.children.clone() }
+
             if let Some(image_url) = &properties.image_url {
                 <img class={yew::classes!("card-image")} src={image_url.clone()}/>
             }
@@ -56,7 +124,7 @@ pub fn card(properties: &Props) -> yew::Html {
             let url = url.clone();
 
             yew::html! {
-                <div onclick={move |_| { let _ = location.set_href(&url); } } class={yew::classes!("card", "hover-scale")}>
+                <div onclick={move |_| { let _ = web_sys::window().unwrap().location().set_href(&url); } } class={yew::classes!("card", "hover-scale")}>
                     {inner}
                 </div>
             }
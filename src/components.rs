@@ -0,0 +1,603 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The custom components markdown pages can use, written like HTML tags:
+//!
+//! - `<Callout kind="note">...</Callout>`, a note, warning or tip set apart from the text, with an
+//!   optional `title`.
+//! - `<Figure src="..." alt="..." caption="..."/>`, an image with an optional caption.
+//! - `<Details summary="...">...</Details>`, collapsed until opened, or from the start with
+//!   `open="true"`.
+//! - `<YouTube id="..." title="..."/>`, a video only loaded from YouTube's privacy-enhanced domain
+//!   once asked for, optionally starting `start` seconds in.
+//! - `<Video src="..." title="..."/>`, a video hosted on the site.
+//! - `<Tabs labels="A, B">` holding a `<Tab>...</Tab>` for each label, only one shown at a time.
+//! - `<Footnote note="..."/>`, a numbered note shown when its number is hovered or focused.
+//! - `<Gist id="..."/>`, the files of a GitHub gist, or only `file`.
+//! - `<Include path="..."/>`, a file hosted on the site shown as code, optionally only `lines`
+//!   of it, in `language` if it's not clear from the file's extension.
+//!
+//! `Gist` and `Include` also take `highlight` like code blocks do. What `UseToc`, `UseAnchor`,
+//! `UseCode` and `UseMath` do is mostly left to the rest of the app, which inserts them itself.
+//!
+//! Components used incorrectly explain what's wrong in their place.
+
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use yew_markdown::{CustomComponents, MdComponentProps};
+use yew_router::components::Link;
+use yew_router::Routable;
+
+use crate::cache::Cache;
+use crate::fetch;
+use crate::highlight::{Code, CodeBlock};
+use crate::math::{Formula, Math};
+use crate::prerender::Prerender;
+use crate::title::Title;
+use crate::toc::{Heading, TableOfContents};
+use crate::Route;
+
+const CALLOUT_KINDS: [&str; 3] = ["note", "tip", "warning"];
+/// The stylesheet only has rules for showing this many tabs.
+const MAX_TABS: usize = 10;
+
+type Component = Rc<dyn Fn(MdComponentProps) -> Result<yew::Html, String>>;
+
+/// Custom components by name, which can be checked for being used correctly without showing them.
+#[derive(Clone, Default)]
+pub struct Components(BTreeMap<&'static str, Component>);
+
+#[derive(PartialEq, yew::Properties)]
+struct TabsProps {
+    labels: Vec<String>,
+    children: yew::Html,
+}
+
+#[derive(PartialEq, yew::Properties)]
+struct YouTubeProps {
+    id: String,
+    title: String,
+    start: Option<u32>,
+}
+
+#[derive(PartialEq, yew::Properties)]
+struct GistProps {
+    id: String,
+    file: Option<String>,
+    highlight: Option<String>,
+}
+
+#[derive(PartialEq, yew::Properties)]
+struct IncludeProps {
+    path: String,
+    lines: Option<(usize, usize)>,
+    language: Option<String>,
+    highlight: Option<String>,
+}
+
+#[derive(Deserialize, PartialEq)]
+struct Gist {
+    files: BTreeMap<String, GistFile>,
+    html_url: String,
+}
+
+#[derive(Deserialize, PartialEq)]
+struct GistFile {
+    content: Option<String>,
+}
+
+/// Every custom component, `UseToc`, `UseCode` and `UseMath` showing the headings, code blocks
+/// and formulas taken out of the page.
+pub fn all(
+    headings: &[Heading],
+    code_blocks: &[CodeBlock],
+    formulas: &[Formula],
+) -> Components {
+    let mut components = Components::default();
+
+    components.register("UseTitle", |props| {
+        let title = required(&props, "title")?;
+        let description = props.get("description");
+        let image = props.get("image");
+
+        Ok(yew::html!( <Title {title} {description} {image} article=true /> ))
+    });
+    components.register("UseAnchor", |props| {
+        let id = required(&props, "id")?;
+
+        Ok(yew::html! {
+            <a class={yew::classes!("anchor")} id={id.clone()} href={format!("#{id}")}>{"#"}</a>
+        })
+    });
+    components.register("UseLink", |props| {
+        let link = required(&props, "link")?;
+        let text = required(&props, "text")?;
+        // paths that aren't routes are recognized as the not found page
+        let Some(route) = Route::recognize(&link).filter(|route| *route != Route::NotFound) else {
+            return Err(format!("`link` isn't a page of the site: {link:?}"));
+        };
+
+        Ok(yew::html!( <Link<Route> to={route}>{text}</Link<Route>> ))
+    });
+    let headings = headings.to_vec();
+    components.register("UseToc", move |_| {
+        Ok(yew::html!( <TableOfContents headings={headings.clone()} /> ))
+    });
+    let code_blocks = code_blocks.to_vec();
+    components.register("UseCode", move |props| {
+        let index = index(&props)?;
+        let Some(block) = code_blocks.get(index) else {
+            return Err(format!("there's no code block {index}"));
+        };
+
+        Ok(yew::html!( <Code block={block.clone()} /> ))
+    });
+    let formulas = formulas.to_vec();
+    components.register("UseMath", move |props| {
+        let index = index(&props)?;
+        let Some(formula) = formulas.get(index) else {
+            return Err(format!("there's no formula {index}"));
+        };
+
+        Ok(yew::html!( <Math formula={formula.clone()} /> ))
+    });
+
+    components.register("Callout", |props| {
+        let kind = props.get("kind").unwrap_or_else(|| "note".to_owned());
+        if !CALLOUT_KINDS.contains(&kind.as_str()) {
+            return Err(format!(
+                "`kind` must be one of {}, not {kind:?}",
+                CALLOUT_KINDS.join(", ")
+            ));
+        }
+
+        let title = props.get("title").unwrap_or_else(|| capitalize(&kind));
+
+        Ok(yew::html! {
+            <aside class={yew::classes!("callout", kind)} role="note">
+                <p class={yew::classes!("callout-title")}>{ title }</p>
+                { props.children }
+            </aside>
+        })
+    });
+    components.register("Figure", |props| {
+        let src = required(&props, "src")?;
+        // an empty `alt` is how an image is marked as decorative, so only leaving it out is wrong
+        let Some(alt) = props.get("alt") else {
+            return Err("`alt` is required, describing the image to those who can't see it".into());
+        };
+        let caption = props.get("caption");
+
+        Ok(yew::html! {
+            <figure class={yew::classes!("figure")}>
+                <img {src} {alt} loading="lazy" />
+                if let Some(caption) = caption {
+                    <figcaption>{ caption }</figcaption>
+                }
+            </figure>
+        })
+    });
+    components.register("Details", |props| {
+        let summary = required(&props, "summary")?;
+        let open = parsed::<bool>(&props, "open", "`true` or `false`")?.unwrap_or(false);
+
+        Ok(yew::html! {
+            <details class={yew::classes!("details")} {open}>
+                <summary>{ summary }</summary>
+                { props.children }
+            </details>
+        })
+    });
+    components.register("YouTube", |props| {
+        let id = required(&props, "id")?;
+        let is_id = id.len() == 11
+            && id
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character));
+        if !is_id {
+            return Err(format!("`id` must be the 11 characters after `v=` in its URL, not {id:?}"));
+        }
+
+        let title = required(&props, "title")?;
+        let start = parsed::<u32>(&props, "start", "a number of seconds")?;
+
+        Ok(yew::html!( <YouTube {id} {title} {start} /> ))
+    });
+    components.register("Video", |props| {
+        let src = required(&props, "src")?;
+        let title = required(&props, "title")?;
+        let poster = props.get("poster");
+
+        Ok(yew::html! {
+            <video
+                class={yew::classes!("video")}
+                src={src.clone()}
+                title={title.clone()}
+                {poster}
+                controls=true
+                preload="metadata"
+            >
+                <a href={src}>{ format!("Download {title}") }</a>
+            </video>
+        })
+    });
+    components.register("Tabs", |props| {
+        let labels = required(&props, "labels")?
+            .split(',')
+            .map(|label| label.trim().to_owned())
+            .collect::<Vec<_>>();
+
+        if labels.iter().any(String::is_empty) {
+            return Err("`labels` must be a comma separated list, without empty labels".into());
+        }
+        if labels.len() > MAX_TABS {
+            return Err(format!("there can only be up to {MAX_TABS} tabs, not {}", labels.len()));
+        }
+
+        Ok(yew::html!( <Tabs {labels}>{ props.children }</Tabs> ))
+    });
+    components.register("Tab", |props| {
+        Ok(yew::html! {
+            <div class={yew::classes!("tab")} role="tabpanel">{ props.children }</div>
+        })
+    });
+    components.register("Footnote", |props| {
+        let note = required(&props, "note")?;
+
+        // the notes are numbered by the stylesheet, so they don't have to know about each other
+        Ok(yew::html! {
+            <span class={yew::classes!("footnote")}>
+                <button
+                    class={yew::classes!("footnote-reference")}
+                    type="button"
+                    aria-label="Footnote"
+                />
+                <span class={yew::classes!("footnote-content")} role="note">{ note }</span>
+            </span>
+        })
+    });
+    components.register("Gist", |props| {
+        let id = required(&props, "id")?;
+        // the ID is what comes last in the gist's URL, which is also accepted whole
+        let id = id.trim_end_matches('/').rsplit('/').next().unwrap_or_default().to_owned();
+        if id.is_empty() || !id.chars().all(|character| character.is_ascii_hexdigit()) {
+            return Err(format!("`id` must be the ID at the end of the gist's URL, not {id:?}"));
+        }
+
+        let file = props.get("file");
+        let highlight = props.get("highlight");
+
+        Ok(yew::html!( <GistView {id} {file} {highlight} /> ))
+    });
+    components.register("Include", |props| {
+        let path = required(&props, "path")?;
+        if !path.starts_with('/') {
+            return Err(format!("`path` must be a path on the site like `/files/x`, not {path:?}"));
+        }
+
+        let lines = match props.get("lines") {
+            None => None,
+            Some(lines) => Some(line_range(&lines).ok_or_else(|| {
+                format!("`lines` must be a range like `3-10`, not {lines:?}")
+            })?),
+        };
+        let language = props.get("language");
+        let highlight = props.get("highlight");
+
+        Ok(yew::html!( <Include {path} {lines} {language} {highlight} /> ))
+    });
+
+    components
+}
+
+impl Components {
+    /// Registers `component` under `name`, which says what's wrong when it's used incorrectly.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        component: impl Fn(MdComponentProps) -> Result<yew::Html, String> + 'static,
+    ) {
+        self.0.insert(name, Rc::new(component));
+    }
+
+    /// What's wrong with using the component `name` with `attributes`, or `None` if there's no
+    /// such component.
+    pub fn check(
+        &self,
+        name: &str,
+        attributes: Vec<(String, String)>,
+    ) -> Option<Result<(), String>> {
+        let component = self.0.get(name)?;
+        let props = MdComponentProps { attributes, children: yew::Html::default() };
+
+        Some(component(props).map(|_| ()))
+    }
+
+    /// The components to render markdown with, each showing the reason in its place when it's
+    /// used incorrectly.
+    pub fn markdown(self) -> CustomComponents {
+        let mut components = CustomComponents::new();
+        for (name, component) in self.0 {
+            components.register(name, move |props| {
+                Ok(component(props).unwrap_or_else(|message| error(name, &message)))
+            });
+        }
+
+        components
+    }
+}
+
+fn error(name: &str, message: &str) -> yew::Html {
+    yew::html! {
+        <span class={yew::classes!("component-error")}>{ format!("<{name}>: {message}") }</span>
+    }
+}
+
+fn required(props: &MdComponentProps, name: &str) -> Result<String, String> {
+    props
+        .get(name)
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| format!("`{name}` is required"))
+}
+
+/// Parses the attribute `name` if it's there, `expected` describing what it should look like.
+fn parsed<T: FromStr>(
+    props: &MdComponentProps,
+    name: &str,
+    expected: &str,
+) -> Result<Option<T>, String> {
+    match props.get(name) {
+        None => Ok(None),
+        Some(value) => match value.trim().parse() {
+            Err(_) => Err(format!("`{name}` must be {expected}, not {value:?}")),
+            Ok(value) => Ok(Some(value)),
+        },
+    }
+}
+
+/// The index the rest of the app gave a component it inserted.
+fn index(props: &MdComponentProps) -> Result<usize, String> {
+    parsed(props, "index", "a whole number")?.ok_or_else(|| "`index` is required".to_owned())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut characters = word.chars();
+
+    match characters.next() {
+        None => String::new(),
+        Some(first) => first.to_uppercase().chain(characters).collect(),
+    }
+}
+
+/// Reads a range of line numbers like `3-10`, or a single one.
+fn line_range(lines: &str) -> Option<(usize, usize)> {
+    let (start, end) = lines.split_once('-').unwrap_or((lines, lines));
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+
+    (start >= 1 && start <= end).then_some((start, end))
+}
+
+/// The fence info of a code block in `language`, highlighting the lines in `highlight`.
+fn info(language: &str, highlight: Option<&str>) -> String {
+    match highlight {
+        None => language.to_owned(),
+        Some(highlight) => format!("{language} highlight={}", highlight.replace(' ', "")),
+    }
+}
+
+/// The extension of `path`, which the grammars can be looked up by.
+fn extension(path: &str) -> &str {
+    path.rsplit_once('.').map_or("", |(_, extension)| extension)
+}
+
+/// Only one tab is shown at a time, the stylesheet hiding the others going by `data-selected`.
+#[yew::function_component(Tabs)]
+fn tabs(props: &TabsProps) -> yew::Html {
+    let selected = yew::use_state_eq(|| 0);
+
+    let buttons = props.labels.iter().enumerate().map(|(index, label)| {
+        let onclick = {
+            let selected = selected.clone();
+            move |_| selected.set(index)
+        };
+
+        yew::html! {
+            <button
+                class={yew::classes!("tab-button")}
+                type="button"
+                role="tab"
+                aria-selected={(*selected == index).to_string()}
+                {onclick}
+            >
+                { label.clone() }
+            </button>
+        }
+    });
+
+    yew::html! {
+        <div class={yew::classes!("tabs")} data-selected={(*selected + 1).to_string()}>
+            <div class={yew::classes!("tab-list")} role="tablist">{ for buttons }</div>
+            <div class={yew::classes!("tab-panels")}>{ props.children.clone() }</div>
+        </div>
+    }
+}
+
+/// Nothing is loaded from YouTube until the video is played, and then from its privacy-enhanced
+/// domain, which doesn't set cookies before then either.
+#[yew::function_component(YouTube)]
+fn youtube(props: &YouTubeProps) -> yew::Html {
+    let loaded = yew::use_state_eq(|| false);
+
+    if *loaded {
+        let mut src = format!("https://www.youtube-nocookie.com/embed/{}?autoplay=1", props.id);
+        if let Some(start) = props.start {
+            src.push_str(&format!("&start={start}"));
+        }
+
+        return yew::html! {
+            <div class={yew::classes!("youtube")}>
+                <iframe
+                    {src}
+                    title={props.title.clone()}
+                    allow="autoplay; encrypted-media; picture-in-picture"
+                    allowfullscreen=true
+                    referrerpolicy="strict-origin-when-cross-origin"
+                />
+            </div>
+        };
+    }
+
+    let onclick = move |_| loaded.set(true);
+
+    yew::html! {
+        <div class={yew::classes!("youtube")}>
+            <button class={yew::classes!("youtube-play")} type="button" {onclick}>
+                <span>{ format!("▶ {}", props.title) }</span>
+                <small>{"Playing the video loads it from YouTube."}</small>
+            </button>
+        </div>
+    }
+}
+
+#[yew::function_component(GistView)]
+fn gist_view(props: &GistProps) -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+
+    let url = format!("https://api.github.com/gists/{}", props.id);
+
+    let gist = fetch::use_fetch(
+        url,
+        |url| {
+            fetch::seed(&prerender, &cache, url, |gist| {
+                serde_json::from_str::<Gist>(gist).ok().map(Some)
+            })
+        },
+        {
+            let cache = cache.clone();
+
+            move |url| {
+                let cache = cache.clone();
+
+                // a missing gist is the page's mistake, so it mustn't send readers to the 404 page
+                async move {
+                    match cache.fetch(&url).await {
+                        Err(error) => match fetch::Error::from(error) {
+                            fetch::Error::NotFound => Ok(None),
+                            error => Err(error),
+                        },
+                        Ok(gist) => Ok(Some(serde_json::from_str::<Gist>(&gist)?)),
+                    }
+                }
+            }
+        },
+    );
+
+    gist.view(|gist| {
+        let Some(gist) = gist else {
+            return error("Gist", &format!("there's no gist with the ID {:?}", props.id));
+        };
+
+        let files = gist
+            .files
+            .iter()
+            .filter(|(name, _)| props.file.as_ref().is_none_or(|file| file == *name))
+            .map(|(name, file)| {
+                // the API leaves out the contents of very large files
+                let Some(content) = &file.content else {
+                    return error("Gist", &format!("{name} is too large to show"));
+                };
+
+                let block = CodeBlock::new(
+                    &info(extension(name), props.highlight.as_deref()),
+                    content.clone(),
+                    &[],
+                );
+
+                yew::html! {
+                    <figure class={yew::classes!("code-file")}>
+                        <Code {block} />
+                        <figcaption>
+                            <a href={gist.html_url.clone()}>{ name.clone() }</a>
+                        </figcaption>
+                    </figure>
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if files.is_empty() {
+            let file = props.file.clone().unwrap_or_default();
+            return error("Gist", &format!("the gist has no file named {file:?}"));
+        }
+
+        yew::html!( <>{ for files }</> )
+    })
+}
+
+#[yew::function_component(Include)]
+fn include(props: &IncludeProps) -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+
+    let file = fetch::use_fetch(
+        props.path.clone(),
+        |path| fetch::seed(&prerender, &cache, path, |file| Some(Some(file.to_owned()))),
+        {
+            let cache = cache.clone();
+
+            move |path| {
+                let cache = cache.clone();
+
+                async move {
+                    match cache.fetch(&path).await {
+                        Err(error) => match fetch::Error::from(error) {
+                            fetch::Error::NotFound => Ok(None),
+                            error => Err(error),
+                        },
+                        Ok(file) => Ok(Some(file)),
+                    }
+                }
+            }
+        },
+    );
+
+    file.view(|file| {
+        let Some(file) = file else {
+            return error("Include", &format!("there's no file at {:?}", props.path));
+        };
+
+        let code = match props.lines {
+            None => file.clone(),
+            Some((start, end)) => {
+                let lines = file.lines().skip(start - 1).take(end - start + 1);
+                lines.map(|line| format!("{line}\n")).collect()
+            }
+        };
+
+        let language = props.language.as_deref().unwrap_or_else(|| extension(&props.path));
+        let block = CodeBlock::new(&info(language, props.highlight.as_deref()), code, &[]);
+
+        yew::html! {
+            <figure class={yew::classes!("code-file")}>
+                <Code {block} />
+                <figcaption><a href={props.path.clone()}>{ props.path.clone() }</a></figcaption>
+            </figure>
+        }
+    })
+}
@@ -1,4 +1,36 @@
-pub const EMAIL: &str = "segmentationviolator@proton.me";
-pub const GITHUB_USERNAME: &str = "SegmentationViolator";
-pub const REPOSITORY_NAME: &str = "WebSegment";
-pub const TITLE: &str = "Web Segment";
+//! The site's configuration, generated from `site.toml` by the build script.
+
+pub struct NavigationEntry {
+    pub title: &'static str,
+    /// The path of the page, which must be absolute.
+    pub path: &'static str,
+}
+
+/// A link to somewhere else the author can be found or contacted, shown in the footer.
+pub struct SocialLink {
+    /// Read out by screen readers, and shown in place of the icon if there's none.
+    pub label: &'static str,
+    pub url: &'static str,
+    /// The Font Awesome classes of the icon, e.g. `fa-brands fa-mastodon`.
+    pub icon: Option<&'static str>,
+    /// Such as `me`, which Mastodon looks for to verify the link.
+    pub rel: Option<&'static str>,
+}
+
+/// The colours of the site, which the stylesheets get as custom properties of the same names.
+#[derive(PartialEq)]
+pub struct Palette {
+    pub name: &'static str,
+    pub background: &'static str,
+    pub foreground: &'static str,
+    pub primary: &'static str,
+    pub secondary_light: &'static str,
+    pub secondary_dark: &'static str,
+    pub error: &'static str,
+    /// A CSS gradient the title in the navigation bar is painted with.
+    pub title_gradient: Option<&'static str>,
+    /// The months, from 1 to 12, the palette replaces the default one during.
+    pub months: &'static [u32],
+}
+
+include!(concat!(env!("OUT_DIR"), "/config.rs"));
@@ -0,0 +1,734 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Pointing out what's wrong with a page where it is: custom components used incorrectly, tags
+//! that aren't custom components, dead links within the site, links to headings that aren't
+//! there and missing images.
+//!
+//! Pages only do so in debug builds, or when opened with `?debug=1`, but the generator checks
+//! every page at build time, when everything the site serves is known.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Parser, Tag};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use yew_router::Routable;
+
+use crate::components::Components;
+use crate::document::Document;
+use crate::Route;
+
+/// The query parameter turning diagnostics on outside of debug builds.
+const DEBUG_PARAMETER: &str = "debug=1";
+
+/// What's known about the rest of the site, anything left out not being checked against.
+#[derive(Default)]
+pub struct Site {
+    /// The filenames of the posts.
+    pub posts: Option<BTreeSet<String>>,
+    /// The names of the projects with a page.
+    pub projects: Option<BTreeSet<String>>,
+    pub tags: Option<BTreeSet<String>>,
+    /// The path of every file the site serves, e.g. `/files/test-post.md`.
+    pub files: Option<BTreeSet<String>>,
+    /// What can be linked to within each page, e.g. its headings, by the page's path.
+    pub anchors: Option<BTreeMap<String, BTreeSet<String>>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    /// A custom component used incorrectly, and what's wrong with it.
+    Component(String, String),
+    DeadLink(String),
+    /// A link to a part of a page that isn't there.
+    MissingAnchor(String),
+    MissingImage(String),
+    /// An image that can only be checked by loading it, as the site's files aren't known.
+    UncheckedImage(String),
+    UnknownComponent(String),
+}
+
+/// A problem with a page, and where it's pointed out.
+#[derive(Clone, PartialEq)]
+pub struct Finding {
+    /// What the problem is with, in the page's body, pointed out just after it.
+    pub range: Range<usize>,
+    pub problem: Problem,
+}
+
+/// What a link is expected to point at.
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    /// A page of the site, or a file it serves.
+    Any,
+    File,
+    Image,
+}
+
+/// The page being checked.
+struct Checker<'a> {
+    components: &'a Components,
+    site: &'a Site,
+    path: &'a str,
+    anchors: &'a BTreeSet<String>,
+}
+
+/// An opening or self-closing tag.
+pub struct Element<'a> {
+    pub name: &'a str,
+    pub attributes: Vec<(String, String)>,
+    /// Where the tag is, in the HTML it's from.
+    pub range: Range<usize>,
+}
+
+impl Element<'_> {
+    pub fn get(&self, attribute: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| name == attribute)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(PartialEq, yew::Properties)]
+pub struct SummaryProps {
+    pub findings: Vec<Finding>,
+}
+
+#[derive(PartialEq, yew::Properties)]
+struct ImageCheckProps {
+    src: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Component(name, message) => write!(f, "<{name}>: {message}"),
+            Self::DeadLink(link) => write!(f, "dead link to {link}"),
+            Self::MissingAnchor(link) => {
+                write!(f, "{link} links to a part of the page that isn't there")
+            }
+            Self::MissingImage(src) | Self::UncheckedImage(src) => write!(f, "missing image {src}"),
+            Self::UnknownComponent(name) => write!(f, "<{name}> isn't a custom component"),
+        }
+    }
+}
+
+/// Whether pages point out what's wrong with them, which they always do in debug builds.
+pub fn enabled() -> bool {
+    let search = web_sys::window().unwrap().location().search().unwrap_or_default();
+
+    cfg!(debug_assertions)
+        || search
+            .trim_start_matches('?')
+            .split('&')
+            .any(|parameter| parameter == DEBUG_PARAMETER)
+}
+
+/// Whether pages point out what's wrong with them, only decided once mounted so hydration sees
+/// what was prerendered.
+#[yew::hook]
+pub fn use_enabled() -> bool {
+    let enabled = yew::use_state_eq(|| false);
+
+    {
+        let enabled = enabled.clone();
+        yew_hooks::use_mount(move || enabled.set(self::enabled()));
+    }
+
+    *enabled
+}
+
+/// What can be linked to within the markdown page `source`, e.g. its headings.
+pub fn anchors(source: &str) -> BTreeSet<String> {
+    Document::new(source, &[], &[]).anchors()
+}
+
+/// What's wrong with the markdown page `source`, served at `path`, along with the line it's on
+/// when it can be told, used by the generator to check pages before they're published.
+pub fn check_page(source: &str, path: &str, site: &Site) -> Vec<(Option<usize>, Problem)> {
+    let document = Document::new(source, &[], &[]);
+    let mut searched = 0;
+    // several problems can be with the same thing
+    let mut previous: Option<(Range<usize>, Option<usize>)> = None;
+
+    document
+        .check(site, path)
+        .into_iter()
+        .map(|finding| {
+            if let Some((range, line)) = &previous {
+                if *range == finding.range {
+                    return (*line, finding.problem);
+                }
+            }
+
+            // links and tags are left as they are in the body, so they can be found in the source,
+            // searching on from the last one in case the same one is used more than once
+            let text = &document.body()[finding.range.clone()];
+            let start = source[searched..]
+                .find(text)
+                .map(|start| searched + start)
+                .or_else(|| source.find(text));
+
+            if let Some(start) = start {
+                searched = start + text.len();
+            }
+
+            let line = start.map(|start| source[..start].matches('\n').count() + 1);
+            previous = Some((finding.range, line));
+
+            (line, finding.problem)
+        })
+        .collect()
+}
+
+/// The `id`s of the elements in `body`, which can be linked to like headings.
+pub(crate) fn ids(body: &str) -> BTreeSet<String> {
+    html(body)
+        .flat_map(|range| elements(&body[range]))
+        .flat_map(|element| element.attributes)
+        .filter(|(name, _)| name == "id")
+        .map(|(_, id)| id)
+        .collect()
+}
+
+/// What's wrong with `body`, the page at `path` as it's rendered with `components`, in order.
+/// `anchors` is what can be linked to within it.
+pub(crate) fn check(
+    body: &str,
+    components: &Components,
+    site: &Site,
+    path: &str,
+    anchors: &BTreeSet<String>,
+) -> Vec<Finding> {
+    let checker = Checker {
+        components,
+        site,
+        path,
+        anchors,
+    };
+    let mut findings = Vec::new();
+
+    for (event, range) in Parser::new(body).into_offset_iter() {
+        let (link, target) = match event {
+            Event::Start(Tag::Link { dest_url, .. }) => (dest_url, Target::Any),
+            Event::Start(Tag::Image { dest_url, .. }) => (dest_url, Target::Image),
+            _ => continue,
+        };
+
+        findings.extend(
+            checker
+                .check_link(&link, target)
+                .map(|problem| Finding { range, problem }),
+        );
+    }
+
+    for html in html(body) {
+        for element in elements(&body[html.clone()]) {
+            let range = html.start + element.range.start..html.start + element.range.end;
+            findings.extend(
+                checker
+                    .check_element(&element)
+                    .into_iter()
+                    .map(|problem| Finding { range: range.clone(), problem }),
+            );
+        }
+    }
+
+    findings.sort_by_key(|finding| finding.range.end);
+    findings
+}
+
+/// Where the HTML in `body` is, taking blocks as a whole as their events are split by line and a
+/// tag might not be.
+fn html(body: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    Parser::new(body)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::HtmlBlock) | Event::InlineHtml(_)))
+        .map(|(_, range)| range)
+}
+
+impl Checker<'_> {
+    fn check_element(&self, element: &Element) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        if element.name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            match self.components.check(element.name, element.attributes.clone()) {
+                None => problems.push(Problem::UnknownComponent(element.name.to_owned())),
+                // its attributes are likely what's wrong with it
+                Some(Err(message)) => {
+                    return vec![Problem::Component(element.name.to_owned(), message)];
+                }
+                Some(Ok(())) => {}
+            }
+        }
+
+        let image = matches!(element.name, "Figure" | "img");
+        for (name, value) in &element.attributes {
+            let target = match name.as_str() {
+                "href" | "link" => Target::Any,
+                "path" if element.name == "Include" => Target::File,
+                "poster" => Target::Image,
+                "src" if image => Target::Image,
+                "src" => Target::File,
+                _ => continue,
+            };
+
+            problems.extend(self.check_link(value, target));
+        }
+
+        problems
+    }
+
+    /// What's wrong with a link to `link`, expected to point at `target`.
+    fn check_link(&self, link: &str, target: Target) -> Option<Problem> {
+        let unchecked = || {
+            (target == Target::Image && self.site.files.is_none())
+                .then(|| Problem::UncheckedImage(link.to_owned()))
+        };
+
+        // links elsewhere can't be known to be dead, but images can still be loaded to find out
+        let Some(resolved) = self.resolve(link) else {
+            return unchecked();
+        };
+
+        let (path, fragment) = match resolved.split_once('#') {
+            None => (resolved.as_str(), None),
+            Some((path, fragment)) => (path, Some(fragment)),
+        };
+        let path = path.split('?').next().unwrap_or_default();
+
+        let known = |names: &Option<BTreeSet<String>>, name: &str| {
+            names.as_ref().is_none_or(|names| names.contains(name))
+        };
+
+        let route = match target {
+            Target::Any => Route::recognize(path),
+            Target::File | Target::Image => None,
+        };
+
+        let found = match route {
+            Some(Route::Post { filename }) => known(&self.site.posts, &filename),
+            Some(Route::Project { name }) => known(&self.site.projects, &name),
+            Some(Route::Tag { tag }) => known(&self.site.tags, &tag),
+            Some(Route::NotFound) | None => match &self.site.files {
+                Some(files) => files.contains(path),
+                None => return unchecked(),
+            },
+            Some(_) => true,
+        };
+
+        if !found {
+            return Some(match target {
+                Target::Image => Problem::MissingImage(link.to_owned()),
+                Target::Any | Target::File => Problem::DeadLink(link.to_owned()),
+            });
+        }
+
+        let anchors = if path == self.path {
+            Some(self.anchors)
+        } else {
+            self.site.anchors.as_ref().and_then(|anchors| anchors.get(path))
+        };
+
+        fragment
+            .filter(|fragment| !fragment.is_empty())
+            .zip(anchors)
+            .filter(|(fragment, anchors)| !anchors.contains(*fragment))
+            .map(|_| Problem::MissingAnchor(link.to_owned()))
+    }
+
+    /// `link` as an absolute path within the site, or `None` if it's to somewhere else.
+    fn resolve(&self, link: &str) -> Option<String> {
+        let external = link
+            .split_once(':')
+            .is_some_and(|(scheme, _)| !scheme.contains(['/', '?', '#']));
+
+        if link.is_empty() || link.starts_with("//") || external {
+            return None;
+        }
+
+        let (path, rest) = link.split_at(link.find(['?', '#']).unwrap_or(link.len()));
+        let path = match path {
+            "" => self.path.to_owned(),
+            path if path.starts_with('/') => path.to_owned(),
+            // relative to the page's directory, like the browser resolves it
+            path => format!("{}{path}", &self.path[..self.path.rfind('/').map_or(0, |i| i + 1)]),
+        };
+
+        let mut segments = Vec::new();
+        for segment in path.split('/').skip(1) {
+            match segment {
+                "." => {}
+                ".." => {
+                    segments.pop();
+                }
+                segment => segments.push(segment),
+            }
+        }
+
+        Some(format!("/{}{rest}", segments.join("/")))
+    }
+}
+
+/// The opening and self-closing tags in `html`, skipping closing tags and comments.
+pub fn elements(html: &str) -> Vec<Element<'_>> {
+    let bytes = html.as_bytes();
+    let skip_whitespace = |mut i: usize| {
+        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut elements = Vec::new();
+    let mut i = 0;
+
+    while let Some(start) = html[i..].find('<').map(|start| i + start) {
+        i = start + 1;
+
+        if html[i..].starts_with("!--") {
+            i = html[i..].find("-->").map_or(html.len(), |end| i + end + 3);
+            continue;
+        }
+
+        if !bytes.get(i).is_some_and(u8::is_ascii_alphabetic) {
+            continue;
+        }
+
+        let name_start = i;
+        while bytes.get(i).is_some_and(|&b| b.is_ascii_alphanumeric() || b == b'-') {
+            i += 1;
+        }
+        let name = &html[name_start..i];
+
+        let mut attributes = Vec::new();
+        let end = loop {
+            i = skip_whitespace(i);
+
+            match bytes.get(i) {
+                None => break None,
+                Some(b'>') => break Some(i + 1),
+                Some(b'/') => i += 1,
+                Some(_) => {
+                    let key_start = i;
+                    while bytes
+                        .get(i)
+                        .is_some_and(|&b| !b.is_ascii_whitespace() && !b"=>/".contains(&b))
+                    {
+                        i += 1;
+                    }
+                    let key = html[key_start..i].to_owned();
+
+                    let mut value = String::new();
+                    let after_key = skip_whitespace(i);
+                    if bytes.get(after_key) == Some(&b'=') {
+                        i = skip_whitespace(after_key + 1);
+
+                        match bytes.get(i) {
+                            Some(&quote) if quote == b'"' || quote == b'\'' => {
+                                let Some(length) = html[i + 1..].find(quote as char) else {
+                                    break None;
+                                };
+                                value = html[i + 1..i + 1 + length].to_owned();
+                                i += length + 2;
+                            }
+                            _ => {
+                                let value_start = i;
+                                while bytes
+                                    .get(i)
+                                    .is_some_and(|&b| !b.is_ascii_whitespace() && b != b'>')
+                                {
+                                    i += 1;
+                                }
+                                value = html[value_start..i].to_owned();
+                            }
+                        }
+                    }
+
+                    attributes.push((key, value));
+                }
+            }
+        };
+
+        match end {
+            None => break,
+            Some(end) => {
+                i = end;
+                elements.push(Element {
+                    name,
+                    attributes,
+                    range: start..end,
+                });
+            }
+        }
+    }
+
+    elements
+}
+
+/// Inserts a `UseDiagnostic` component after what each of `findings` is about, except for custom
+/// components used incorrectly, which explain what's wrong themselves.
+pub(crate) fn mark(body: &str, findings: &[Finding]) -> String {
+    let mut body = body.to_owned();
+
+    // from the end, so the offsets of the rest stay valid
+    for (index, finding) in findings.iter().enumerate().rev() {
+        if !matches!(finding.problem, Problem::Component(..)) {
+            body.insert_str(finding.range.end, &format!("<UseDiagnostic index=\"{index}\"/>"));
+        }
+    }
+
+    body
+}
+
+/// Registers `UseDiagnostic`, pointing out one of `findings`.
+pub(crate) fn register(components: &mut Components, findings: &[Finding]) {
+    let problems = findings
+        .iter()
+        .map(|finding| finding.problem.clone())
+        .collect::<Vec<_>>();
+
+    components.register("UseDiagnostic", move |props| {
+        let index = props.get_parsed::<usize>("index")?;
+        let Some(problem) = problems.get(index) else {
+            return Err(format!("there's no diagnostic {index}"));
+        };
+
+        Ok(match problem {
+            Problem::UncheckedImage(src) => yew::html!( <ImageCheck src={src.clone()} /> ),
+            problem => marker(problem),
+        })
+    });
+}
+
+fn marker(problem: &Problem) -> yew::Html {
+    yew::html! {
+        <mark class={yew::classes!("diagnostic")}>
+            <i class={yew::classes!("fa-solid", "fa-triangle-exclamation")}></i>
+            { format!(" {problem}") }
+        </mark>
+    }
+}
+
+/// Lists what's wrong with the page, or that nothing is, above it.
+#[yew::function_component(Summary)]
+pub fn summary(props: &SummaryProps) -> yew::Html {
+    // images that couldn't be checked ahead of time are only pointed out if they fail to load
+    let problems = props
+        .findings
+        .iter()
+        .map(|finding| &finding.problem)
+        .filter(|problem| !matches!(problem, Problem::UncheckedImage(_)))
+        .map(|problem| yew::html!( <li>{ problem.to_string() }</li> ))
+        .collect::<Vec<_>>();
+
+    let status = match problems.len() {
+        0 => "no problems found".to_owned(),
+        1 => "1 problem found".to_owned(),
+        count => format!("{count} problems found"),
+    };
+
+    yew::html! {
+        <aside class={yew::classes!("diagnostics")}>
+            <p><strong>{"Diagnostics: "}</strong>{ status }</p>
+            if !problems.is_empty() {
+                <ul>
+                    { for problems }
+                </ul>
+            }
+        </aside>
+    }
+}
+
+/// Points out the image `src` if it fails to load.
+#[yew::function_component(ImageCheck)]
+fn image_check(props: &ImageCheckProps) -> yew::Html {
+    let missing = yew::use_state_eq(|| false);
+
+    {
+        let missing = missing.clone();
+
+        yew::use_effect_with(props.src.clone(), move |src| {
+            missing.set(false);
+
+            let image = web_sys::HtmlImageElement::new().unwrap();
+            let listener = Closure::<dyn Fn()>::new(move || missing.set(true));
+
+            image.set_onerror(Some(listener.as_ref().unchecked_ref()));
+            image.set_src(src);
+
+            move || {
+                image.set_onerror(None);
+                drop(listener);
+            }
+        });
+    }
+
+    if !*missing {
+        return yew::html!(<></>);
+    }
+
+    marker(&Problem::MissingImage(props.src.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes(html: &str) -> Vec<(&str, Vec<(String, String)>)> {
+        elements(html)
+            .into_iter()
+            .map(|element| (element.name, element.attributes))
+            .collect()
+    }
+
+    fn pair(name: &str, value: &str) -> (String, String) {
+        (name.to_owned(), value.to_owned())
+    }
+
+    fn resolve(path: &str, link: &str) -> Option<String> {
+        let checker = Checker {
+            components: &Components::default(),
+            site: &Site::default(),
+            path,
+            anchors: &BTreeSet::new(),
+        };
+
+        checker.resolve(link)
+    }
+
+    #[test]
+    fn reads_quoted_unquoted_and_valueless_attributes() {
+        assert_eq!(
+            attributes(r#"<Figure src="/a b.png" alt='a "b"' width=10 hidden/>"#),
+            [(
+                "Figure",
+                vec![
+                    pair("src", "/a b.png"),
+                    pair("alt", "a \"b\""),
+                    pair("width", "10"),
+                    pair("hidden", ""),
+                ],
+            )],
+        );
+        assert_eq!(
+            attributes("<Callout kind = \"tip\" open>"),
+            [("Callout", vec![pair("kind", "tip"), pair("open", "")])],
+        );
+    }
+
+    #[test]
+    fn records_where_tags_are() {
+        let html = "text <a href=\"/\">link</a> <Bogus/>";
+        let ranges = elements(html)
+            .into_iter()
+            .map(|element| &html[element.range])
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, ["<a href=\"/\">", "<Bogus/>"]);
+    }
+
+    #[test]
+    fn skips_less_than_signs_in_text() {
+        assert_eq!(
+            attributes("1 < 2 and 3 <4, <b>bold</b> <"),
+            [("b", Vec::new())],
+        );
+    }
+
+    #[test]
+    fn skips_closing_tags_and_comments() {
+        assert_eq!(
+            attributes("</Callout><!-- <Hidden/> --><Shown/><!-- unterminated <Gone/>"),
+            [("Shown", Vec::new())],
+        );
+    }
+
+    #[test]
+    fn stops_at_unterminated_tags() {
+        assert!(attributes("<Figure src=\"/a.png").is_empty());
+    }
+
+    #[test]
+    fn resolves_relative_links_against_the_page() {
+        let page = "/post/test-post.md";
+
+        assert_eq!(resolve(page, "test-post-two.md").as_deref(), Some("/post/test-post-two.md"));
+        assert_eq!(resolve(page, "./test-post-two.md").as_deref(), Some("/post/test-post-two.md"));
+        assert_eq!(resolve(page, "../assets/a.png").as_deref(), Some("/assets/a.png"));
+        assert_eq!(resolve(page, "../../../a.png").as_deref(), Some("/a.png"));
+        assert_eq!(resolve(page, "/tag/./rust/../yew").as_deref(), Some("/tag/yew"));
+        assert_eq!(resolve(page, "../posts?page=2#top").as_deref(), Some("/posts?page=2#top"));
+    }
+
+    #[test]
+    fn resolves_fragment_only_links_to_the_page() {
+        let page = "/post/test-post.md";
+
+        assert_eq!(resolve(page, "#math").as_deref(), Some("/post/test-post.md#math"));
+        assert_eq!(resolve(page, "?a=1").as_deref(), Some("/post/test-post.md?a=1"));
+    }
+
+    #[test]
+    fn leaves_links_elsewhere_alone() {
+        let page = "/post/test-post.md";
+
+        for link in ["", "https://example.com/a", "//example.com/a", "mailto:a@b.c"] {
+            assert_eq!(resolve(page, link), None, "{link}");
+        }
+    }
+
+    #[test]
+    fn checks_fragments_against_the_page() {
+        let source = "# Math\n\n$$x \\label{x}$$\n\n<span id=\"own\"></span>\n\n\
+            [a](#math) [b](#eq-x) [c](#own) [d](#nope) [e](test-post.md#nope)\n";
+
+        assert_eq!(
+            check_page(source, "/post/test-post.md", &Site::default()),
+            [
+                (Some(7), Problem::MissingAnchor("#nope".to_owned())),
+                (Some(7), Problem::MissingAnchor("test-post.md#nope".to_owned())),
+            ],
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_each_repeated_link() {
+        let source = "+++\ntitle = \"Test\"\n+++\n\n[gone](/post/gone.md)\n\n\
+            ```rs\nfn main() {}\n```\n\n[gone](/post/gone.md) <Bogus/>\n\n\
+            <Figure src=\"/a.png\" alt=\"a\"/>\n<Figure src=\"/a.png\" alt=\"a\"/>\n";
+        let site = Site {
+            posts: Some(BTreeSet::new()),
+            files: Some(BTreeSet::new()),
+            ..Site::default()
+        };
+
+        assert_eq!(
+            check_page(source, "/post/test.md", &site),
+            [
+                (Some(5), Problem::DeadLink("/post/gone.md".to_owned())),
+                (Some(11), Problem::DeadLink("/post/gone.md".to_owned())),
+                (Some(11), Problem::UnknownComponent("Bogus".to_owned())),
+                (Some(13), Problem::MissingImage("/a.png".to_owned())),
+                (Some(14), Problem::MissingImage("/a.png".to_owned())),
+            ],
+        );
+    }
+}
@@ -0,0 +1,97 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeSet;
+
+use yew_markdown::Markdown;
+
+use crate::components::{self, Components};
+use crate::diagnostics::{self, Finding, Site};
+use crate::highlight::{self, CodeBlock, Highlighting};
+use crate::math::{self, Formula};
+use crate::toc::{self, Heading};
+use crate::utils;
+
+/// A markdown page, with what its custom components show taken out of it.
+#[derive(PartialEq)]
+pub struct Document {
+    body: String,
+    code_blocks: Vec<CodeBlock>,
+    formulas: Vec<Formula>,
+    headings: Vec<Heading>,
+}
+
+impl Document {
+    /// `math` and `highlighting` are the page's formulas and code blocks as rendered at build time,
+    /// if there are any.
+    pub fn new(source: &str, math: &[math::Rendered], highlighting: &[Highlighting]) -> Self {
+        let body = utils::strip_front_matter(source);
+        let (body, code_blocks) = highlight::extract_code_blocks(body, highlighting);
+        let (body, formulas) = math::extract_formulas(&body, &math::macros(source), math);
+        let (body, headings) = toc::anchor_headings(&body);
+
+        Self {
+            body,
+            code_blocks,
+            formulas,
+            headings,
+        }
+    }
+
+    /// The page as it's rendered, with custom components in place of what was taken out of it.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    fn components(&self) -> Components {
+        components::all(&self.headings, &self.code_blocks, &self.formulas)
+    }
+
+    /// What can be linked to within the page: its headings, labelled formulas and elements with
+    /// an `id`.
+    pub fn anchors(&self) -> BTreeSet<String> {
+        let headings = self.headings.iter().map(|heading| heading.slug.clone());
+        let formulas = self.formulas.iter().filter_map(|formula| formula.id().map(str::to_owned));
+
+        headings.chain(formulas).chain(diagnostics::ids(&self.body)).collect()
+    }
+
+    /// What's wrong with the page at `path`, judged by what's known about the rest of `site`.
+    pub fn check(&self, site: &Site, path: &str) -> Vec<Finding> {
+        diagnostics::check(&self.body, &self.components(), site, path, &self.anchors())
+    }
+
+    /// Shows the page, pointing out each of `findings` where it is when there are any.
+    pub fn view(&self, findings: Option<&[Finding]>) -> yew::Html {
+        let mut components = self.components();
+
+        let Some(findings) = findings else {
+            let components = components.markdown();
+            return yew::html!( <Markdown src={self.body.clone()} {components} /> );
+        };
+
+        let src = diagnostics::mark(&self.body, findings);
+        diagnostics::register(&mut components, findings);
+        let components = components.markdown();
+
+        yew::html! {
+            <>
+                <diagnostics::Summary findings={findings.to_vec()} />
+                <Markdown {src} {components} />
+            </>
+        }
+    }
+}
@@ -0,0 +1,372 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::Cell;
+use std::future::Future;
+use std::rc::Rc;
+use std::time::Duration;
+
+use yew_router::components::Redirect;
+
+use crate::cache::Cache;
+use crate::prerender::Prerender;
+use crate::Route;
+
+/// How many times a fetch failing for a transient reason is retried before giving up, waiting twice
+/// as long each time.
+const AUTOMATIC_RETRIES: u32 = 4;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Why a fetch failed.
+#[derive(Clone, PartialEq)]
+pub enum Error {
+    /// The response wasn't in the expected format.
+    Decode(String),
+    /// No response arrived, most likely because the connection dropped.
+    Network(String),
+    NotFound,
+    /// The server answered with an error status.
+    Status(u16),
+    /// A third party service explained what went wrong itself.
+    Upstream(String),
+}
+
+impl Error {
+    /// Whether fetching again might succeed without anything else changing.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Network(_) => true,
+            Self::Status(status) => *status == 408 || *status == 429 || *status >= 500,
+            Self::Decode(_) | Self::NotFound | Self::Upstream(_) => false,
+        }
+    }
+
+    /// Explains the error to readers, who don't care for the details.
+    fn message(&self) -> String {
+        match self {
+            Self::Decode(_) => "Got a response that couldn't be read.".to_owned(),
+            Self::Network(_) => "Couldn't connect, check your connection.".to_owned(),
+            Self::NotFound => "Couldn't find what you were looking for.".to_owned(),
+            Self::Status(429) => "Too many requests were made, wait a bit.".to_owned(),
+            Self::Status(status) if *status >= 500 => {
+                format!("The server ran into a problem ({status}).")
+            }
+            Self::Status(status) => format!("The server refused the request ({status})."),
+            Self::Upstream(detail) => format!("The service responded with: {detail}"),
+        }
+    }
+
+    /// The underlying error, for those who do care.
+    fn details(&self) -> Option<&str> {
+        match self {
+            Self::Decode(details) | Self::Network(details) => Some(details),
+            Self::NotFound | Self::Status(_) | Self::Upstream(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(reqwest::StatusCode::NOT_FOUND) => Self::NotFound,
+            Some(status) => Self::Status(status.as_u16()),
+            None if error.is_decode() => Self::Decode(error.to_string()),
+            None => Self::Network(error.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Decode(error.to_string())
+    }
+}
+
+/// Data available before fetching, which is shown straight away.
+pub enum Seed<T> {
+    /// Prerendered along with the page, so it's as new as the page itself.
+    Fresh(T),
+    /// Cached from an earlier visit, so it's fetched again in the background.
+    Stale(T),
+}
+
+impl<T> Seed<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Fresh(data) | Self::Stale(data) => data,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Seed<U> {
+        match self {
+            Self::Fresh(data) => Seed::Fresh(f(data)),
+            Self::Stale(data) => Seed::Stale(f(data)),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+pub enum Status<T> {
+    Complete(Rc<T>),
+    Error {
+        error: Error,
+        /// Whether it's going to be fetched again without having to retry manually.
+        retrying: bool,
+    },
+    Loading,
+    NotFound,
+}
+
+impl<T> Clone for Status<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Complete(data) => Self::Complete(data.clone()),
+            Self::Error { error, retrying } => Self::Error {
+                error: error.clone(),
+                retrying: *retrying,
+            },
+            Self::Loading => Self::Loading,
+            Self::NotFound => Self::NotFound,
+        }
+    }
+}
+
+struct State<K, T> {
+    key: K,
+    /// Bumped on every retry, so responses to earlier attempts are told apart.
+    attempt: u32,
+    status: Status<T>,
+    /// Whether what's shown still has to be fetched, or fetched again.
+    stale: bool,
+}
+
+impl<K: Clone, T> Clone for State<K, T> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            attempt: self.attempt,
+            status: self.status.clone(),
+            stale: self.stale,
+        }
+    }
+}
+
+impl<K, T> State<K, T> {
+    fn seeded(key: K, seed: Option<Seed<T>>) -> Self {
+        let (status, stale) = match seed {
+            None => (Status::Loading, true),
+            Some(Seed::Fresh(data)) => (Status::Complete(Rc::new(data)), false),
+            Some(Seed::Stale(data)) => (Status::Complete(Rc::new(data)), true),
+        };
+
+        Self {
+            key,
+            attempt: 0,
+            status,
+            stale,
+        }
+    }
+}
+
+enum Action<K, T> {
+    Replace(State<K, T>),
+    /// Carries whether a failed fetch is about to be retried automatically.
+    Resolve(K, u32, Result<T, Error>, bool),
+    Retry,
+}
+
+impl<K: Clone + PartialEq, T: PartialEq> yew::Reducible for State<K, T> {
+    type Action = Action<K, T>;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        match action {
+            Action::Replace(state) => Rc::new(state),
+            Action::Resolve(key, attempt, result, retrying) => {
+                // the fetch was for a key or attempt that's since been moved on from
+                if key != self.key || attempt != self.attempt {
+                    return self;
+                }
+
+                let status = match (&self.status, result) {
+                    (Status::Complete(data), Ok(fetched)) if **data == fetched => return self,
+                    // a failed revalidation leaves what's shown up
+                    (Status::Complete(_), Err(_)) => return self,
+                    (_, Ok(fetched)) => Status::Complete(Rc::new(fetched)),
+                    (_, Err(Error::NotFound)) => Status::NotFound,
+                    (_, Err(error)) => Status::Error { error, retrying },
+                };
+
+                Rc::new(Self {
+                    key,
+                    attempt,
+                    status,
+                    stale: false,
+                })
+            }
+            Action::Retry => Rc::new(Self {
+                key: self.key.clone(),
+                attempt: self.attempt + 1,
+                status: Status::Loading,
+                stale: true,
+            }),
+        }
+    }
+}
+
+pub struct UseFetchHandle<T> {
+    pub status: Status<T>,
+    retry: yew::Callback<()>,
+}
+
+impl<T> UseFetchHandle<T> {
+    /// Renders the fetched data with `complete`, and everything else the same way on every page.
+    pub fn view(&self, complete: impl FnOnce(&T) -> yew::Html) -> yew::Html {
+        match &self.status {
+            Status::Complete(data) => complete(data),
+            Status::Error { error, retrying } => {
+                let retry = self.retry.reform(|_: yew::MouseEvent| ());
+
+                yew::html! {
+                    <div class={yew::classes!("status", "error")}>
+                        <p>
+                            { error.message() }
+                            if *retrying {
+                                {" Trying again shortly..."}
+                            }
+                            <button class={yew::classes!("retry")} onclick={retry}>{"Retry"}</button>
+                        </p>
+                        if let Some(details) = error.details() {
+                            <small>{ details.to_owned() }</small>
+                        }
+                    </div>
+                }
+            }
+            Status::Loading => {
+                yew::html!( <p class={yew::classes!("status")}>{"Fetching..."}</p> )
+            }
+            Status::NotFound => yew::html!( <Redirect<Route> to={Route::NotFound} /> ),
+        }
+    }
+}
+
+/// Fetches the data identified by `key` with `fetch`, starting from what `seed` returns for it, and
+/// fetching again whenever the key changes, ignoring responses for keys moved on from.
+#[yew::hook]
+pub fn use_fetch<K, T, S, F, Fut>(key: K, seed: S, fetch: F) -> UseFetchHandle<T>
+where
+    K: Clone + PartialEq + 'static,
+    T: PartialEq + 'static,
+    S: FnOnce(&K) -> Option<Seed<T>>,
+    F: Fn(K) -> Fut + 'static,
+    Fut: Future<Output = Result<T, Error>> + 'static,
+{
+    let mut seed = Some(seed);
+    let state = yew::use_reducer(|| {
+        let seed = seed.take().expect("seed should only be taken once");
+        State::seeded(key.clone(), seed(&key))
+    });
+
+    // until the effect below catches up, a new key is shown as seeded rather than with the data of
+    // the previous one
+    let (current, replaced) = match state.key == key {
+        true => ((*state).clone(), false),
+        false => {
+            let seed = seed.take().expect("seed should only be taken once");
+            (State::seeded(key.clone(), seed(&key)), true)
+        }
+    };
+
+    {
+        let current = current.clone();
+        let dispatcher = state.dispatcher();
+
+        yew::use_effect_with((key, current.attempt), move |(key, attempt)| {
+            let cancelled = Rc::new(Cell::new(false));
+
+            if replaced {
+                dispatcher.dispatch(Action::Replace(current.clone()));
+            }
+
+            if current.stale {
+                let cancelled = cancelled.clone();
+                let (key, attempt) = (key.clone(), *attempt);
+
+                // failing to revalidate leaves what's shown up, so there's no need to keep trying
+                let mut retries = match current.status {
+                    Status::Complete(_) => 0,
+                    _ => AUTOMATIC_RETRIES,
+                };
+
+                yew::platform::spawn_local(async move {
+                    let mut backoff = INITIAL_BACKOFF;
+
+                    loop {
+                        let result = fetch(key.clone()).await;
+                        if cancelled.get() {
+                            return;
+                        }
+
+                        let retrying = retries > 0
+                            && matches!(&result, Err(error) if error.is_transient());
+                        dispatcher.dispatch(Action::Resolve(key.clone(), attempt, result, retrying));
+
+                        if !retrying {
+                            return;
+                        }
+
+                        yew::platform::time::sleep(backoff).await;
+                        if cancelled.get() {
+                            return;
+                        }
+
+                        retries -= 1;
+                        backoff *= 2;
+                    }
+                });
+            }
+
+            move || cancelled.set(true)
+        });
+    }
+
+    let retry = {
+        let dispatcher = state.dispatcher();
+        yew::Callback::from(move |()| dispatcher.dispatch(Action::Retry))
+    };
+
+    UseFetchHandle {
+        status: current.status.clone(),
+        retry,
+    }
+}
+
+/// Seeds a fetch of `path` from the response the page was prerendered with, or else from the
+/// cache.
+pub fn seed<T>(
+    prerender: &Prerender,
+    cache: &Cache,
+    path: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Option<Seed<T>> {
+    if let Some(data) = prerender.response(path).and_then(|response| parse(&response)) {
+        return Some(Seed::Fresh(data));
+    }
+
+    cache
+        .get(path)
+        .and_then(|response| parse(&response))
+        .map(Seed::Stale)
+}
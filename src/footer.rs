@@ -18,23 +18,33 @@ use crate::config;
 
 #[yew::function_component(Footer)]
 pub fn footer() -> yew::Html {
+    let links = config::SOCIAL_LINKS.iter().map(|link| {
+        yew::html! {
+            <>
+                <span class={yew::classes!("separator")}>{"|"}</span>
+                <a href={link.url} rel={link.rel} aria-label={link.label} title={link.label}>
+                    if let Some(icon) = link.icon {
+                        <i class={icon} aria-hidden="true"></i>
+                    } else {
+                        { link.label }
+                    }
+                </a>
+            </>
+        }
+    });
+
     yew::html! {
         <div class={yew::classes!("footer")}>
             <p>
                 {"Made with "}
                 <span style="white-space: nowrap;">
                     <a href="https://yew.rs/">{"Yew"}</a>
-                    <span class={yew::classes!("separator")}>{"|"}</span>
-                    <a href={format!("https://github.com/{}", config::GITHUB_USERNAME)}>
-                        <i class={yew::classes!("fa-brands", "fa-github")}></i>
-                    </a>
-                    <span class={yew::classes!("separator")}>{"|"}</span>
-                    <a href={format!("mailto:{}", config::EMAIL)}>
-                        <i class={yew::classes!("fa-solid", "fa-envelope")}></i>
-                    </a>
+                    { for links }
                 </span>
             </p>
-            <a href={format!("https://github.com/{}/{}", config::GITHUB_USERNAME, config::REPOSITORY_NAME)}>{"Source Code"}</a>
+            if let Some(repository) = config::REPOSITORY {
+                <a href={repository}>{"Source Code"}</a>
+            }
         </div>
     }
 }
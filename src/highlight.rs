@@ -0,0 +1,386 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Code blocks, highlighted by the generator at build time, since syntect's grammars are too
+//! large to send to the browser. Code the app only gets while it runs, like `Gist`s, is shown
+//! without highlighting.
+
+use std::collections::BTreeSet;
+#[cfg(feature = "ssr")]
+use std::sync::OnceLock;
+
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+#[cfg(feature = "ssr")]
+use syntect::util::LinesWithEndings;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::cache::Cache;
+use crate::fetch;
+use crate::prerender::Prerender;
+
+/// Keeps the highlighting classes from clashing with the site's own.
+#[cfg(feature = "ssr")]
+const CLASS_PREFIX: &str = "hl-";
+/// How long the copy button says the code was copied for, in milliseconds.
+const COPIED_DURATION: u32 = 2000;
+
+/// A run of text within a line, along with the classes of the scopes it's in.
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
+struct Token {
+    classes: String,
+    text: String,
+}
+
+/// The tokens of a code block highlighted at build time.
+#[derive(Deserialize, Serialize)]
+pub struct Highlighting {
+    /// Of the block's language and code, so the tokens are only used for the code they're of,
+    /// even if the page and its highlighting were cached at different times.
+    fingerprint: u64,
+    lines: Vec<Vec<Token>>,
+}
+
+/// A fenced code block, highlighted ahead of time.
+///
+/// Besides the language, the fence takes `highlight=2,4-6` to highlight lines, and `numbers=false`
+/// to leave out the line numbers.
+#[derive(Clone, PartialEq)]
+pub struct CodeBlock {
+    code: String,
+    highlighted: BTreeSet<usize>,
+    language: Option<String>,
+    lines: Vec<Vec<Token>>,
+    numbered: bool,
+}
+
+impl CodeBlock {
+    /// `info` is what follows the opening fence, the language along with any attributes. The
+    /// block is highlighted if it's among `highlighting`, and left plain otherwise.
+    pub fn new(info: &str, code: String, highlighting: &[Highlighting]) -> Self {
+        let mut words = info.split_whitespace();
+        let language = words.next().map(str::to_owned);
+
+        let mut highlighted = BTreeSet::new();
+        let mut numbered = true;
+
+        for word in words {
+            match word.split_once('=') {
+                Some(("highlight", lines)) => highlighted.extend(line_numbers(lines)),
+                Some(("numbers", numbers)) => numbered = numbers != "false",
+                _ => {}
+            }
+        }
+
+        let fingerprint = fingerprint(language.as_deref(), &code);
+        let lines = highlighting
+            .iter()
+            .find(|highlighting| highlighting.fingerprint == fingerprint)
+            .map_or_else(|| plain(&code), |highlighting| highlighting.lines.clone());
+
+        Self {
+            lines,
+            code,
+            highlighted,
+            language,
+            numbered,
+        }
+    }
+}
+
+/// Reads a list of line numbers and ranges of them, like `2,4-6`, skipping anything else.
+fn line_numbers(list: &str) -> impl Iterator<Item = usize> + '_ {
+    list.split(',')
+        .filter_map(|part| {
+            let (start, end) = part.split_once('-').unwrap_or((part, part));
+            Some(start.trim().parse().ok()?..=end.trim().parse().ok()?)
+        })
+        .flatten()
+}
+
+/// FNV-1a, as the generator and the app have to agree on it, which the standard library's hashers
+/// don't promise across platforms.
+fn fingerprint(language: Option<&str>, code: &str) -> u64 {
+    let language = language.unwrap_or_default();
+
+    language
+        .bytes()
+        .chain([0])
+        .chain(code.bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+fn plain(code: &str) -> Vec<Vec<Token>> {
+    code.lines()
+        .map(|line| {
+            vec![Token {
+                classes: String::new(),
+                text: line.to_owned(),
+            }]
+        })
+        .collect()
+}
+
+/// Where the highlighting done at build time for the markdown file at `page` is kept.
+pub fn path(page: &str) -> String {
+    format!("/code{}.json", page.strip_prefix("/files").unwrap_or(page))
+}
+
+fn parse(json: &str) -> Option<Vec<Highlighting>> {
+    serde_json::from_str(json).ok()
+}
+
+/// The highlighting done at build time for the markdown file at `page`, left empty unless the
+/// page was prerendered with it or it's been cached.
+pub fn seed(prerender: &Prerender, cache: &Cache, page: &str) -> Vec<Highlighting> {
+    fetch::seed(prerender, cache, &path(page), parse)
+        .map(fetch::Seed::into_inner)
+        .unwrap_or_default()
+}
+
+/// Fetches the highlighting done at build time for the markdown file at `page`, leaving it empty
+/// if it can't be, since the code is still readable without it.
+pub async fn fetch(cache: &Cache, page: &str) -> Vec<Highlighting> {
+    match cache.fetch(&path(page)).await {
+        Err(_) => Vec::new(),
+        Ok(json) => parse(&json).unwrap_or_default(),
+    }
+}
+
+/// Highlights the code blocks of the page `source`, for the generator to write out at build time.
+#[cfg(feature = "ssr")]
+pub fn prerender(source: &str) -> Vec<Highlighting> {
+    let (_, blocks) = extract_code_blocks(crate::utils::strip_front_matter(source), &[]);
+
+    blocks
+        .into_iter()
+        .map(|block| Highlighting {
+            fingerprint: fingerprint(block.language.as_deref(), &block.code),
+            lines: tokenize(block.language.as_deref(), &block.code),
+        })
+        .collect()
+}
+
+/// The grammars bundled with syntect, which take a while to load, so they're only loaded once.
+#[cfg(feature = "ssr")]
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Splits `code` into lines of tokens, using the grammar for `language` if there is one.
+#[cfg(feature = "ssr")]
+fn tokenize(language: Option<&str>, code: &str) -> Vec<Vec<Token>> {
+    let syntaxes = syntaxes();
+    let syntax = language
+        .and_then(|language| syntaxes.find_syntax_by_token(language))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+
+    LinesWithEndings::from(code)
+        .map(|line| {
+            // a grammar failing on a line leaves it unhighlighted rather than the whole block
+            let operations = state.parse_line(line, syntaxes).unwrap_or_default();
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            let mut tokens = Vec::new();
+            let mut start = 0;
+
+            for (offset, operation) in operations {
+                let offset = offset.min(line.len());
+                if offset > start {
+                    tokens.push(token(&line[start..offset], &stack));
+                    start = offset;
+                }
+
+                let _ = stack.apply(&operation);
+            }
+
+            if start < line.len() {
+                tokens.push(token(&line[start..], &stack));
+            }
+
+            tokens
+        })
+        .collect()
+}
+
+/// Tags `text` with every part of the scopes it's in, e.g. `hl-keyword hl-control hl-rust` for
+/// `keyword.control.rust`, so the stylesheet can pick out tokens as broadly as it likes.
+#[cfg(feature = "ssr")]
+fn token(text: &str, stack: &ScopeStack) -> Token {
+    let mut classes = Vec::<String>::new();
+
+    // the first scope is the grammar's own, covering the whole block
+    for scope in stack.as_slice().iter().skip(1) {
+        for atom in scope.build_string().split('.') {
+            let class = format!("{CLASS_PREFIX}{atom}");
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+    }
+
+    Token {
+        classes: classes.join(" "),
+        text: text.to_owned(),
+    }
+}
+
+/// Replaces every fenced code block in `source` with a `UseCode` component, returning the
+/// modified source along with the code blocks found, highlighted if they're among `highlighting`.
+pub fn extract_code_blocks(
+    source: &str,
+    highlighting: &[Highlighting],
+) -> (String, Vec<CodeBlock>) {
+    let mut blocks = Vec::new();
+    let mut replacements = Vec::new();
+
+    // (info string, code, range of the whole block)
+    let mut current: Option<(String, String, std::ops::Range<usize>)> = None;
+
+    for (event, range) in Parser::new(source).into_offset_iter() {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                current = Some((info.into_string(), String::new(), range));
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let Some((info, code, range)) = current.take() else {
+                    continue;
+                };
+
+                // a blank line keeps whatever follows the block out of the component's HTML
+                // block, which would otherwise run until the next blank line
+                let mut component = format!("<UseCode index=\"{}\"/>", blocks.len());
+                let followed_by_blank_line = source[range.end..]
+                    .lines()
+                    .nth(1)
+                    .is_none_or(|line| line.trim().is_empty());
+                if !followed_by_blank_line {
+                    component.push('\n');
+                }
+
+                replacements.push((range, component));
+                blocks.push(CodeBlock::new(&info, code, highlighting));
+            }
+            Event::Text(text) => {
+                if let Some((_, code, _)) = current.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut source = source.to_owned();
+    for (range, component) in replacements.into_iter().rev() {
+        source.replace_range(range, &component);
+    }
+
+    (source, blocks)
+}
+
+#[derive(PartialEq, yew::Properties)]
+pub struct Props {
+    pub block: CodeBlock,
+}
+
+/// Shows a code block with its line numbers and highlighted lines, and a button to copy it.
+#[yew::function_component(Code)]
+pub fn code(props: &Props) -> yew::Html {
+    let block = &props.block;
+    let copied = yew::use_state_eq(|| false);
+
+    let timeout = {
+        let copied = copied.clone();
+        yew_hooks::use_timeout(move || copied.set(false), COPIED_DURATION)
+    };
+
+    let onclick = {
+        let code = block.code.clone();
+        let copied = copied.clone();
+
+        move |_| {
+            // navigator.clipboard is missing outside of secure contexts
+            let clipboard = web_sys::window().unwrap().navigator().clipboard();
+            if clipboard.is_undefined() {
+                return;
+            }
+
+            let promise = clipboard.write_text(&code);
+            let copied = copied.clone();
+            timeout.reset();
+
+            yew::platform::spawn_local(async move {
+                if JsFuture::from(promise).await.is_ok() {
+                    copied.set(true);
+                }
+            });
+        }
+    };
+
+    let digits = block.lines.len().to_string().len();
+
+    let lines = block.lines.iter().enumerate().map(|(index, tokens)| {
+        let number = index + 1;
+
+        let mut classes = yew::classes!("line");
+        if block.highlighted.contains(&number) {
+            classes.push("highlighted");
+        }
+
+        let tokens = tokens.iter().map(|token| {
+            if token.classes.is_empty() {
+                return yew::html!({ token.text.clone() });
+            }
+
+            yew::html!( <span class={token.classes.clone()}>{ token.text.clone() }</span> )
+        });
+
+        // the number is drawn by the stylesheet so selecting the code doesn't pick it up
+        yew::html! {
+            <span class={classes}>
+                if block.numbered {
+                    <span
+                        class={yew::classes!("line-number")}
+                        data-line={number.to_string()}
+                        aria-hidden="true"
+                    />
+                }
+                { for tokens }
+            </span>
+        }
+    });
+
+    let language = block.language.as_ref().map(|language| format!("language-{language}"));
+
+    yew::html! {
+        <div class={yew::classes!("code-block")}>
+            <button class={yew::classes!("copy-button")} type="button" {onclick}>
+                { if *copied { "Copied" } else { "Copy" } }
+            </button>
+            <pre style={format!("--digits: {digits}")}>
+                <code class={yew::classes!(language)}>{ for lines }</code>
+            </pre>
+        </div>
+    }
+}
@@ -0,0 +1,52 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+use crate::cache::Cache;
+use crate::fetch;
+
+pub const PATH: &str = "/posts.json";
+
+/// An entry of `posts.json`, which the generator builds from the front matter of every post.
+#[derive(Clone, Deserialize, PartialEq)]
+pub struct Post {
+    pub title: String,
+    pub date: String,
+    pub filename: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+/// Parses the index out of a prerendered or cached response.
+pub fn parse(json: &str) -> Option<Vec<Post>> {
+    serde_json::from_str(json).ok()
+}
+
+/// Fetches the index, treating a missing `posts.json` as a site without posts.
+pub async fn fetch(cache: &Cache) -> Result<Vec<Post>, fetch::Error> {
+    match cache.fetch(PATH).await {
+        Err(error) => match fetch::Error::from(error) {
+            fetch::Error::NotFound => Ok(Vec::new()),
+            error => Err(error),
+        },
+        Ok(posts) => Ok(serde_json::from_str(&posts)?),
+    }
+}
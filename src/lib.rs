@@ -0,0 +1,285 @@
+// web segment - a personal website used to host some text files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use stylist::manager::StyleManager;
+use stylist::yew::ManagerProvider;
+use yew::ContextProvider;
+use yew_router::{BrowserRouter, Routable, Switch};
+
+mod cache;
+mod card;
+mod components;
+pub mod config;
+pub mod diagnostics;
+mod document;
+mod fetch;
+mod footer;
+pub mod highlight;
+mod index;
+pub mod math;
+mod navigation_bar;
+mod offline_indicator;
+mod pages;
+mod prerender;
+pub mod search;
+mod theme;
+mod title;
+mod toc;
+
+pub mod utils;
+
+use cache::Cache;
+use footer::Footer;
+use navigation_bar::NavigationBar;
+use offline_indicator::OfflineIndicator;
+use prerender::Prerender;
+use theme::ThemeProvider;
+
+#[cfg(feature = "ssr")]
+pub use prerender::Page;
+
+#[derive(Clone, Debug, PartialEq, Routable)]
+pub enum Route {
+    #[at("/")]
+    Home,
+    #[not_found]
+    #[at("/404.html")]
+    NotFound,
+    #[at("/post/:filename")]
+    Post { filename: String },
+    #[at("/posts")]
+    Posts,
+    #[at("/project/:name")]
+    Project { name: String },
+    #[at("/projects")]
+    Projects,
+    #[at("/tag/:tag")]
+    Tag { tag: String },
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Kept in the session storage once the splash has been shown.
+const SPLASHED_KEY: &str = "splashed";
+
+#[derive(PartialEq, yew::Properties)]
+struct AppProps {
+    prerender: Prerender,
+    splash: bool,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(PartialEq, yew::Properties)]
+struct ServerAppProps {
+    manager: StyleManager,
+    prerender: Prerender,
+    url: String,
+}
+
+/// Everything inside the router, shared by the browser and the prerenderer so hydration sees the
+/// same tree either way.
+#[yew::function_component(Layout)]
+fn layout() -> yew::Html {
+    let route = yew_router::hooks::use_route::<Route>();
+
+    yew::use_effect_with(route, |_| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let app = document.get_element_by_id("App").unwrap();
+
+        match app.class_name().as_str() {
+            "fade" => app.set_class_name("fade-again"),
+            _ => app.set_class_name("fade"),
+        }
+    });
+
+    yew::html! {
+        <ThemeProvider>
+            <NavigationBar />
+            <OfflineIndicator />
+            <div class={yew::classes!("body")}>
+                <Switch<Route> render={switch} />
+            </div>
+            <Footer />
+        </ThemeProvider>
+    }
+}
+
+#[yew::function_component(App)]
+fn app(props: &AppProps) -> yew::Html {
+    let cache = yew::use_state(Cache::default);
+    let manager = yew::use_state(StyleManager::default);
+    let splashed = yew::use_state_eq(|| !props.splash);
+
+    let timeout = {
+        let splashed = splashed.clone();
+        yew_hooks::use_timeout(move || splashed.set(true), config::SPLASH_DURATION)
+    };
+
+    {
+        let splashed = splashed.clone();
+        yew_hooks::use_event_with_window("keydown", move |_: web_sys::Event| splashed.set(true));
+    }
+
+    if *splashed {
+        timeout.cancel();
+
+        return yew::html! {
+            <ManagerProvider manager={(*manager).clone()}>
+                <ContextProvider<Prerender> context={props.prerender.clone()}>
+                    <ContextProvider<Cache> context={(*cache).clone()}>
+                        <div id="App">
+                            <BrowserRouter>
+                                <Layout />
+                            </BrowserRouter>
+                        </div>
+                    </ContextProvider<Cache>>
+                </ContextProvider<Prerender>>
+            </ManagerProvider>
+        };
+    }
+
+    yew::html! {
+        <>
+            <div id="Splash" onclick={move |_| splashed.set(true)}>
+                <h1 id="Splash-inner">{ config::TITLE }</h1>
+            </div>
+        </>
+    }
+}
+
+/// The prerendered counterpart of `App`, which the latter hydrates.
+#[cfg(feature = "ssr")]
+#[yew::function_component(ServerApp)]
+fn server_app(props: &ServerAppProps) -> yew::Html {
+    use yew_router::history::{AnyHistory, History, MemoryHistory};
+
+    let cache = yew::use_state(Cache::default);
+    let history = AnyHistory::from(MemoryHistory::new());
+    history.push(props.url.clone());
+
+    yew::html! {
+        <ManagerProvider manager={props.manager.clone()}>
+            <ContextProvider<Prerender> context={props.prerender.clone()}>
+                <ContextProvider<Cache> context={(*cache).clone()}>
+                    <div id="App">
+                        <yew_router::Router history={history}>
+                            <Layout />
+                        </yew_router::Router>
+                    </div>
+                </ContextProvider<Cache>>
+            </ContextProvider<Prerender>>
+        </ManagerProvider>
+    }
+}
+
+fn switch(route: Route) -> yew::Html {
+    match route {
+        Route::Home => pages::home(),
+        Route::NotFound => pages::not_found(),
+        Route::Post { filename } => pages::post(filename),
+        Route::Posts => pages::posts(None),
+        Route::Project { name } => pages::project(name),
+        Route::Projects => pages::projects(),
+        Route::Tag { tag } => pages::posts(Some(tag)),
+    }
+}
+
+/// Whether to show the splash, which is only worth it on the first page of a session and is left
+/// out for readers who prefer reduced motion. The session is marked as splashed along the way.
+fn splash_once() -> bool {
+    if !config::SPLASH {
+        return false;
+    }
+
+    let window = web_sys::window().unwrap();
+
+    let reduced_motion = window
+        .match_media("(prefers-reduced-motion: reduce)")
+        .ok()
+        .flatten()
+        .is_some_and(|query| query.matches());
+    if reduced_motion {
+        return false;
+    }
+
+    // without session storage, e.g. in some private windows, every page load counts as the first
+    let Some(storage) = window.session_storage().ok().flatten() else {
+        return true;
+    };
+
+    if storage.get_item(SPLASHED_KEY).ok().flatten().is_some() {
+        return false;
+    }
+
+    let _ = storage.set_item(SPLASHED_KEY, "true");
+    true
+}
+
+/// Mounts the app, hydrating the page if it was prerendered for the current location.
+pub fn start() {
+    let document = web_sys::window().unwrap().document().unwrap();
+
+    #[cfg(feature = "hydration")]
+    {
+        let prerender = Prerender::from_document(&document);
+
+        if prerender.is_for_current_location() {
+            yew::Renderer::<App>::with_props(AppProps {
+                prerender,
+                splash: false,
+            })
+            .hydrate();
+            return;
+        }
+    }
+
+    // e.g. 404.html being served for a path that wasn't prerendered
+    if let Some(body) = document.body() {
+        body.set_inner_html("");
+    }
+
+    yew::Renderer::<App>::with_props(AppProps {
+        prerender: Prerender::default(),
+        splash: splash_once(),
+    })
+    .render();
+}
+
+#[cfg(test)]
+mod tests {
+    use yew_router::Routable;
+
+    use super::*;
+
+    #[test]
+    fn build_script_knows_every_route() {
+        let not_found = Route::NotFound.to_path();
+        let mut routes = Route::routes();
+        routes.retain(|route| *route != not_found);
+        routes.sort_unstable();
+
+        let mut known = config::ROUTES.to_vec();
+        known.sort_unstable();
+
+        assert_eq!(routes, known);
+    }
+}
@@ -0,0 +1,435 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pulldown_cmark::{Event, Options, Parser};
+use pulldown_latex::config::DisplayMode;
+use pulldown_latex::{RenderConfig, Storage};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+use crate::prerender::Prerender;
+use crate::{config, fetch, highlight, utils};
+
+/// How many times macros are expanded within each other before giving up on a formula, in case
+/// one of them expands to itself.
+const EXPANSION_LIMIT: usize = 16;
+
+/// The part of a page's front matter the formulas care about, e.g.
+///
+/// ```toml
+/// [macros]
+/// R = "\\mathbb{R}"
+/// norm = "\\left\\lVert #1 \\right\\rVert"
+/// ```
+#[derive(Default, Deserialize)]
+struct FrontMatter {
+    #[serde(default)]
+    macros: BTreeMap<String, String>,
+}
+
+/// A `$` or `$$` delimited formula, rendered to MathML.
+///
+/// Display formulas are numbered unless they contain `\notag` or `\nonumber`, or are given a
+/// number of their own with `\tag`. A `\label{name}` lets other formulas refer to them with
+/// `\ref{name}` or `\eqref{name}`, and links to them as `#eq-name`.
+#[derive(Clone, PartialEq)]
+pub struct Formula {
+    display: bool,
+    id: Option<String>,
+    latex: String,
+    /// Missing if the formula couldn't be rendered.
+    mathml: Option<String>,
+    number: Option<String>,
+}
+
+impl Formula {
+    /// The id links to the formula use, if it has a `\label`.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn display(&self) -> bool {
+        self.display
+    }
+
+    /// The LaTeX the formula was rendered from, with its macros expanded and references resolved.
+    pub fn latex(&self) -> &str {
+        &self.latex
+    }
+
+    pub fn mathml(&self) -> Option<&str> {
+        self.mathml.as_deref()
+    }
+
+    /// The number display formulas are shown with, without the parentheses around it.
+    pub fn number(&self) -> Option<&str> {
+        self.number.as_deref()
+    }
+}
+
+/// A formula rendered at build time, along with what it was rendered from, so it's only used for
+/// that formula even if the page and its formulas were cached at different times.
+#[derive(Deserialize, Serialize)]
+pub struct Rendered {
+    display: bool,
+    latex: String,
+    mathml: Option<String>,
+}
+
+/// Where the MathML rendered at build time for the markdown file at `page` is kept.
+pub fn path(page: &str) -> String {
+    format!("/math{}.json", page.strip_prefix("/files").unwrap_or(page))
+}
+
+fn parse(json: &str) -> Option<Vec<Rendered>> {
+    serde_json::from_str(json).ok()
+}
+
+/// The MathML rendered at build time for the markdown file at `page`, left empty unless the page
+/// was prerendered with it or it's been cached.
+pub fn seed(prerender: &Prerender, cache: &Cache, page: &str) -> Vec<Rendered> {
+    if !config::PRERENDER_MATH {
+        return Vec::new();
+    }
+
+    fetch::seed(prerender, cache, &path(page), parse)
+        .map(fetch::Seed::into_inner)
+        .unwrap_or_default()
+}
+
+/// Fetches the MathML rendered at build time for the markdown file at `page`, leaving it empty if
+/// it can't be, since the formulas can still be rendered on the spot.
+pub async fn fetch(cache: &Cache, page: &str) -> Vec<Rendered> {
+    if !config::PRERENDER_MATH {
+        return Vec::new();
+    }
+
+    match cache.fetch(&path(page)).await {
+        Err(_) => Vec::new(),
+        Ok(json) => parse(&json).unwrap_or_default(),
+    }
+}
+
+/// Renders the formulas of the page `source` the same way the app does, for the generator to
+/// write out at build time.
+pub fn prerender(source: &str) -> Vec<Rendered> {
+    let (body, _) = highlight::extract_code_blocks(utils::strip_front_matter(source), &[]);
+
+    extract_formulas(&body, &macros(source), &[])
+        .1
+        .into_iter()
+        .map(|formula| Rendered {
+            display: formula.display,
+            latex: formula.latex,
+            mathml: formula.mathml,
+        })
+        .collect()
+}
+
+/// The macros defined in the front matter of the page `source`.
+pub fn macros(source: &str) -> BTreeMap<String, String> {
+    let front_matter = utils::split_front_matter(source)
+        .and_then(|(front_matter, _)| toml::from_str::<FrontMatter>(front_matter).ok())
+        .unwrap_or_default();
+
+    front_matter
+        .macros
+        .into_iter()
+        .map(|(name, body)| (name.trim_start_matches('\\').to_owned(), body))
+        .collect()
+}
+
+/// Replaces every formula in `body` with a `UseMath` component, returning the modified body along
+/// with the formulas found. Their MathML is taken from `prerendered` when it has them, and rendered
+/// on the spot otherwise.
+pub fn extract_formulas(
+    body: &str,
+    macros: &BTreeMap<String, String>,
+    prerendered: &[Rendered],
+) -> (String, Vec<Formula>) {
+    let mut formulas = Vec::new();
+    let mut ranges = Vec::new();
+    let mut labels = HashMap::new();
+    let mut count = 0;
+
+    for (event, range) in Parser::new_ext(body, Options::ENABLE_MATH).into_offset_iter() {
+        let (latex, display) = match event {
+            Event::DisplayMath(latex) => (latex, true),
+            Event::InlineMath(latex) => (latex, false),
+            _ => continue,
+        };
+
+        let mut label = None;
+        let mut tag = None;
+        let mut numbered = display;
+
+        let latex = rewrite(&expand(&latex, macros), |name, rest| {
+            match name {
+                "label" => {
+                    let (argument, rest) = group(rest.trim_start())?;
+                    label = Some(argument.trim().to_owned());
+                    Some((String::new(), rest))
+                }
+                "tag" => {
+                    let (argument, rest) = group(rest.trim_start())?;
+                    tag = Some(argument.trim().to_owned());
+                    Some((String::new(), rest))
+                }
+                "notag" | "nonumber" => {
+                    numbered = false;
+                    Some((String::new(), rest))
+                }
+                _ => None,
+            }
+        });
+
+        let number = match tag {
+            Some(tag) => Some(tag),
+            None if numbered => {
+                count += 1;
+                Some(count.to_string())
+            }
+            None => None,
+        };
+
+        if let (Some(label), Some(number)) = (&label, &number) {
+            labels.insert(label.clone(), number.clone());
+        }
+
+        ranges.push(range);
+        formulas.push(Formula {
+            display,
+            id: label.map(|label| format!("eq-{label}")),
+            latex,
+            mathml: None,
+            number,
+        });
+    }
+
+    for formula in &mut formulas {
+        // references can point forward, so they're only resolved once every formula is numbered
+        formula.latex = rewrite(&formula.latex, |name, rest| {
+            let parenthesized = match name {
+                "eqref" => true,
+                "ref" => false,
+                _ => return None,
+            };
+
+            let (label, rest) = group(rest.trim_start())?;
+            let number = labels.get(label.trim()).map_or("??", String::as_str);
+            let text = match parenthesized {
+                false => number.to_owned(),
+                true => format!("({number})"),
+            };
+
+            Some((format!("\\text{{{text}}}"), rest))
+        });
+
+        formula.mathml = match prerendered.iter().find(|rendered| {
+            rendered.latex == formula.latex && rendered.display == formula.display
+        }) {
+            None => to_mathml(&formula.latex, formula.display),
+            Some(rendered) => rendered.mathml.clone(),
+        };
+    }
+
+    let mut body = body.to_owned();
+    for (index, range) in ranges.into_iter().enumerate().rev() {
+        body.replace_range(range, &format!("<UseMath index=\"{index}\"/>"));
+    }
+
+    (body, formulas)
+}
+
+fn to_mathml(latex: &str, display: bool) -> Option<String> {
+    let storage = Storage::new();
+    let parser = pulldown_latex::Parser::new(latex, &storage);
+    let config = RenderConfig {
+        display_mode: match display {
+            false => DisplayMode::Inline,
+            true => DisplayMode::Block,
+        },
+        ..RenderConfig::default()
+    };
+
+    let mut mathml = String::new();
+    pulldown_latex::push_mathml(&mut mathml, parser, config).ok()?;
+
+    Some(mathml)
+}
+
+/// Expands the macros in `latex`, whose arguments, `#1` to `#9` in their definitions, are given in
+/// braces or as single characters like in TeX.
+fn expand(latex: &str, macros: &BTreeMap<String, String>) -> String {
+    let mut latex = latex.to_owned();
+
+    for _ in 0..EXPANSION_LIMIT {
+        let mut expanded = false;
+
+        latex = rewrite(&latex, |name, rest| {
+            let definition = macros.get(name)?;
+            let (arguments, rest) = arguments(rest, arity(definition))?;
+            expanded = true;
+
+            let mut expansion = definition.clone();
+            for (index, argument) in arguments.iter().enumerate() {
+                expansion = expansion.replace(&format!("#{}", index + 1), argument);
+            }
+
+            Some((expansion, rest))
+        });
+
+        if !expanded {
+            break;
+        }
+    }
+
+    latex
+}
+
+/// How many arguments a macro takes, going by the highest one its definition uses.
+fn arity(definition: &str) -> usize {
+    definition
+        .split('#')
+        .skip(1)
+        .filter_map(|rest| rest.chars().next()?.to_digit(10))
+        .max()
+        .unwrap_or(0) as usize
+}
+
+/// Reads `count` arguments off the start of `text`, returning them and the text after them.
+fn arguments(text: &str, count: usize) -> Option<(Vec<&str>, &str)> {
+    let mut arguments = Vec::with_capacity(count);
+    let mut rest = text;
+
+    for _ in 0..count {
+        let trimmed = rest.trim_start();
+
+        let (argument, after) = match group(trimmed) {
+            Some(group) => group,
+            None => {
+                let length = trimmed.chars().next()?.len_utf8();
+                trimmed.split_at(length)
+            }
+        };
+
+        arguments.push(argument);
+        rest = after;
+    }
+
+    Some((arguments, rest))
+}
+
+/// Reads a brace delimited group off the start of `text`, returning its contents and the text
+/// after it.
+fn group(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix('{')?;
+    let mut depth = 0;
+    let mut escaped = false;
+
+    for (offset, character) in rest.char_indices() {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some((&rest[..offset], &rest[offset + 1..])),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Rewrites the commands in `latex` that `replace` returns a replacement for. It's given the name
+/// of every command and the text after it, and returns the replacement along with the text left
+/// after any arguments it used.
+fn rewrite<'a>(
+    latex: &'a str,
+    mut replace: impl FnMut(&str, &'a str) -> Option<(String, &'a str)>,
+) -> String {
+    let mut output = String::with_capacity(latex.len());
+    let mut rest = latex;
+
+    while let Some(start) = rest.find('\\') {
+        output.push_str(&rest[..start]);
+
+        let after = &rest[start + 1..];
+        let length = after
+            .find(|character: char| !character.is_ascii_alphabetic())
+            .unwrap_or(after.len());
+
+        // control symbols like `\\` and `\{` can't be macros, and are kept as they are
+        if length == 0 {
+            let symbol = after.chars().next().map_or(0, char::len_utf8);
+            output.push_str(&rest[start..start + 1 + symbol]);
+            rest = &after[symbol..];
+            continue;
+        }
+
+        let (name, arguments) = after.split_at(length);
+
+        match replace(name, arguments) {
+            None => {
+                output.push('\\');
+                output.push_str(name);
+                rest = arguments;
+            }
+            Some((replacement, remaining)) => {
+                output.push_str(&replacement);
+                rest = remaining;
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[derive(PartialEq, yew::Properties)]
+pub struct Props {
+    pub formula: Formula,
+}
+
+/// Shows a formula, along with its number if it's a numbered display formula.
+#[yew::function_component(Math)]
+pub fn math(props: &Props) -> yew::Html {
+    let formula = &props.formula;
+
+    let math = match &formula.mathml {
+        Some(mathml) => yew::Html::from_html_unchecked(mathml.clone().into()),
+        // shown as written rather than not at all
+        None => yew::html! {
+            <code class={yew::classes!("math-error")}>{ formula.latex.clone() }</code>
+        },
+    };
+
+    if !formula.display {
+        return math;
+    }
+
+    // a span, as display formulas can sit in the middle of a paragraph
+    yew::html! {
+        <span class={yew::classes!("equation")} id={formula.id.clone()}>
+            { math }
+            if let Some(number) = &formula.number {
+                <span class={yew::classes!("equation-number")}>{ format!("({number})") }</span>
+            }
+        </span>
+    }
+}
@@ -16,8 +16,10 @@
 
 use yew::classes;
 use yew_router::components::Link;
+use yew_router::Routable;
 
 use crate::config;
+use crate::theme::{ThemeContext, ThemeToggle};
 use crate::Route;
 
 #[stylist::yew::styled_component(NavigationBar)]
@@ -27,34 +29,40 @@ pub fn navigation_bar() -> yew::Html {
 
     let disabled_link = stylist::yew::use_style!("pointer-events: none;");
 
-    let pages = Route::DISPLAYABLE
+    let pages = config::NAVIGATION
         .iter()
-        .map(|route| {
+        // paths that aren't routes are recognized as the not found page
+        .filter_map(|entry| {
+            let route = Route::recognize(entry.path).filter(|route| *route != Route::NotFound)?;
+            Some((entry, route))
+        })
+        .map(|(entry, route)| {
             let mut classes = classes!("nav-link");
-            if current_route == *route {
+            if current_route == route {
                 classes.push("active");
                 classes.push(disabled_link.clone());
             }
 
             yew::html! {
                 <li>
-                    <Link<Route> classes={classes} to={route.clone()}>
-                        <small> {route.to_string()} </small>
+                    <Link<Route> classes={classes} to={route}>
+                        <small> {entry.title} </small>
                     </Link<Route>>
                 </li>
             }
         })
         .collect::<yew::Html>();
 
-    #[allow(unused_mut)]
-    let mut classes = classes!("nav-head");
+    let palette = yew::use_context::<ThemeContext>().unwrap_or_default().palette;
+    let gradient = stylist::yew::use_style!(
+        "background-clip: text; background-image: ${gradient}; color: transparent;",
+        gradient = palette.title_gradient.unwrap_or("none"),
+    );
 
-    #[cfg(feature = "pri-demon-th")]
-    classes.push(stylist::yew::use_style!(
-        "background-clip: text;\
-        background-image: linear-gradient(to top, #770088 25%, #004CFF 35%, #028121 45%, #FFEE00 55%, #FF8D00 65%, #E50000 75%);\
-        color: transparent;"
-    ));
+    let mut classes = classes!("nav-head");
+    if palette.title_gradient.is_some() {
+        classes.push(gradient);
+    }
 
     yew::html! {
         <div class={yew::classes!("nav-bar")}>
@@ -63,6 +71,7 @@ pub fn navigation_bar() -> yew::Html {
             </h2>
             <ui class={yew::classes!("nav-links")}>
                 { pages }
+                <li><ThemeToggle /></li>
             </ui>
         </div>
     }
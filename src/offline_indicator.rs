@@ -0,0 +1,82 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+
+/// Posted by the service worker when it answers with a cached copy because the network failed.
+const SERVED_FROM_CACHE: &str = "served-from-cache";
+
+/// Lets readers know that what they're reading might be out of date, either because the browser is
+/// offline or because the service worker could only serve cached copies.
+#[yew::function_component(OfflineIndicator)]
+pub fn offline_indicator() -> yew::Html {
+    // starts out online so hydration sees what was prerendered
+    let offline = yew::use_state(|| false);
+
+    {
+        let offline = offline.clone();
+        yew_hooks::use_mount(move || {
+            offline.set(!web_sys::window().unwrap().navigator().on_line());
+        });
+    }
+
+    {
+        let offline = offline.clone();
+        yew_hooks::use_event_with_window("offline", move |_: web_sys::Event| offline.set(true));
+    }
+
+    {
+        let offline = offline.clone();
+        yew_hooks::use_event_with_window("online", move |_: web_sys::Event| offline.set(false));
+    }
+
+    {
+        let offline = offline.clone();
+
+        yew::use_effect_with((), move |_| {
+            let listener = Closure::<dyn Fn(web_sys::MessageEvent)>::new(
+                move |event: web_sys::MessageEvent| {
+                    if let Some(message) = event.data().as_string() {
+                        offline.set(message == SERVED_FROM_CACHE);
+                    }
+                },
+            );
+
+            // navigator.serviceWorker is missing outside of secure contexts, making these fail
+            let container = web_sys::window().unwrap().navigator().service_worker();
+            let _ = container
+                .add_event_listener_with_callback("message", listener.as_ref().unchecked_ref());
+
+            move || {
+                let _ = container.remove_event_listener_with_callback(
+                    "message",
+                    listener.as_ref().unchecked_ref(),
+                );
+            }
+        });
+    }
+
+    if !*offline {
+        return yew::html!(<></>);
+    }
+
+    yew::html! {
+        <p class={yew::classes!("status", "offline")}>
+            {"You're offline, so you might be reading an older copy of this page."}
+        </p>
+    }
+}
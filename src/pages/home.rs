@@ -14,15 +14,37 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::config;
+use crate::theme::{Theme, ThemeContext};
 use crate::title::Title;
 
 #[stylist::yew::styled_component(Home)]
 pub fn home() -> yew::Html {
-    let break_condition = stylist::yew::use_media_query("(max-width: 508px)");
-    let hi_image_url = if stylist::yew::use_media_query("(prefers-color-scheme: dark)") {
-        "/assets/hi_dark.gif"
-    } else {
-        "/assets/hi_light.gif"
+    let theme = yew::use_context::<ThemeContext>()
+        .map(|context| context.theme)
+        .unwrap_or_default();
+
+    let line_break = css!("@media (min-width: 509px) { display: none; }");
+    let greeting = config::GREETING.iter().enumerate().map(|(index, line)| {
+        yew::html! {
+            <>
+                if index > 0 {
+                    <br class={line_break.clone()}/>
+                }
+                { format!("{line} ") }
+            </>
+        }
+    });
+
+    // the system's theme is left to a media query so that prerendered pages match what the
+    // browser renders
+    let greeting_image = match theme {
+        Theme::Light => css!("background-image: url(/assets/hi_light.gif);"),
+        Theme::Dark => css!("background-image: url(/assets/hi_dark.gif);"),
+        Theme::System => css!(
+            "background-image: url(/assets/hi_light.gif);\
+            @media (prefers-color-scheme: dark) { background-image: url(/assets/hi_dark.gif); }"
+        ),
     };
 
     yew::html! {
@@ -30,26 +52,21 @@ pub fn home() -> yew::Html {
             <Title title="Home" />
 
             <div
-                class={css!("width: 100%; height: 25vh; background-position: center; background-repeat: no-repeat;")}
-                style={format!("background-image: url({})", hi_image_url)}
+                class={yew::classes!(
+                    css!("width: 100%; height: 25vh; background-position: center; background-repeat: no-repeat;"),
+                    greeting_image,
+                )}
             >
             </div>
 
             <p class={css!("font-size: 1.5rem; hyphens: none; line-break: normal; text-align: center;")}>
-                {"I am SegV, "}
-
-                if break_condition {
-                    <br/>
-                }
-
-                {"a hobbyist programmer."}
+                { for greeting }
             </p>
 
             <br/>
 
             <p style="text-align: center;">
-                {"I am particularly interested in systems programming, computers graphics and game development. "}
-                {"I consider my expertise level to be intermediate and have a lot to learn."}
+                { config::BIO }
             </p>
 
             <br/><br/>
@@ -2,10 +2,12 @@ mod home;
 mod not_found;
 mod post;
 mod posts;
+mod project;
 mod projects;
 
 pub use home::home;
 pub use not_found::not_found;
 pub use post::post;
 pub use posts::posts;
+pub use project::project;
 pub use projects::projects;
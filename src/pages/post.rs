@@ -14,19 +14,27 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use yew_markdown::Markdown;
 use yew_router::components::Link;
-use yew_router::components::Redirect;
 use yew_router::Routable;
 
+use crate::cache::Cache;
+use crate::diagnostics::{self, Site};
+use crate::document::Document;
+use crate::fetch;
+use crate::highlight::{self, Highlighting};
+use crate::index;
+use crate::math;
+use crate::prerender::Prerender;
 use crate::title::Title;
-use crate::utils;
 use crate::Route;
 
-struct PostView {
-    body: Option<String>,
-    fetch_state: utils::FetchState,
-    filename: String,
+const RELATED_POSTS: usize = 3;
+
+#[derive(PartialEq)]
+struct Content {
+    document: Document,
+    /// The post index, newest first, left empty if it couldn't be fetched.
+    posts: Vec<index::Post>,
 }
 
 #[derive(PartialEq, yew::Properties)]
@@ -34,126 +42,191 @@ struct Props {
     pub filename: String,
 }
 
-impl yew::Component for PostView {
-    type Message = utils::Message<String, String>;
-    type Properties = Props;
-
-    fn create(ctx: &yew::Context<Self>) -> Self {
+impl Content {
+    fn new(
+        post: &str,
+        posts: Vec<index::Post>,
+        math: &[math::Rendered],
+        highlighting: &[Highlighting],
+    ) -> Self {
         Self {
-            body: None,
-            fetch_state: utils::FetchState::Pending,
-            filename: ctx.props().filename.clone(),
+            document: Document::new(post, math, highlighting),
+            posts,
         }
     }
 
-    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
-        match msg {
-            utils::Message::FetchData => {
-                let filename = ctx.props().filename.clone();
-
-                ctx.link().send_future(async move {
-                    let base = web_sys::window().unwrap().location().origin().unwrap();
-
-                    let post = match reqwest::get(format!("{base}/files/{filename}"))
-                        .await
-                        .and_then(|response| response.error_for_status())
-                    {
-                        Err(error) => {
-                            if let Some(reqwest::StatusCode::NOT_FOUND) = error.status() {
-                                return utils::Message::SetState(utils::FetchState::NotFound);
-                            }
-
-                            return utils::Message::SetState(utils::FetchState::Error(
-                                error.to_string(),
-                            ));
-                        }
-                        Ok(response) => match response.text().await {
-                            Err(error) => {
-                                return utils::Message::SetState(utils::FetchState::Error(
-                                    error.to_string(),
-                                ))
-                            }
-                            Ok(text) => text,
-                        },
-                    };
-
-                    utils::Message::SetContent(post)
-                });
-
-                self.fetch_state = utils::FetchState::Ongoing;
-                true
-            }
-            utils::Message::SetContent(post) => {
-                let _ = self.body.insert(post);
+    /// The post's position and entry in the index, missing for posts that haven't been indexed.
+    fn entry(&self, filename: &str) -> Option<(usize, &index::Post)> {
+        self.posts
+            .iter()
+            .enumerate()
+            .find(|(_, post)| post.filename == filename)
+    }
 
-                self.fetch_state = utils::FetchState::Complete;
-                true
-            }
-            utils::Message::SetState(state) => {
-                self.fetch_state = state;
-                true
-            }
-            utils::Message::UpdateData(filename) => {
-                self.filename = filename;
-                false
+    /// Links to the posts before and after this one, and to the ones sharing the most tags with
+    /// it, so readers don't dead-end at the bottom of a post.
+    fn navigation(&self, filename: &str) -> yew::Html {
+        let Some((position, entry)) = self.entry(filename) else {
+            return yew::html!(<></>);
+        };
+
+        let link = |post: &index::Post| {
+            yew::html! {
+                <Link<Route> to={Route::Post { filename: post.filename.clone() }}>
+                    { post.title.clone() }
+                </Link<Route>>
             }
+        };
+
+        // the index is sorted newest first
+        let previous = self.posts.get(position + 1);
+        let next = position.checked_sub(1).and_then(|position| self.posts.get(position));
+
+        let mut related = self
+            .posts
+            .iter()
+            .filter(|post| post.filename != entry.filename)
+            .map(|post| {
+                let shared = post.tags.iter().filter(|tag| entry.tags.contains(tag)).count();
+                (shared, post)
+            })
+            .filter(|(shared, _)| *shared > 0)
+            .collect::<Vec<_>>();
+
+        // stable, so posts sharing as many tags stay newest first
+        related.sort_by(|(a, _), (b, _)| b.cmp(a));
+        related.truncate(RELATED_POSTS);
+
+        yew::html! {
+            <nav class={yew::classes!("post-navigation")}>
+                <div class={yew::classes!("adjacent-posts")}>
+                    if let Some(previous) = previous {
+                        <span class={yew::classes!("previous")}>{"← "}{ link(previous) }</span>
+                    }
+                    if let Some(next) = next {
+                        <span class={yew::classes!("next")}>{ link(next) }{" →"}</span>
+                    }
+                </div>
+                if !related.is_empty() {
+                    <h3>{"Related posts"}</h3>
+                    <ul>
+                        { for related.into_iter().map(|(_, post)| yew::html!( <li>{ link(post) }</li> )) }
+                    </ul>
+                }
+            </nav>
         }
     }
 
-    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
-        match &self.fetch_state {
-            utils::FetchState::Complete => {
-                let filename = ctx.props().filename.clone();
+    /// What's known about the rest of the site from the index, if it could be fetched.
+    fn site(&self, filename: &str) -> Site {
+        // the post itself is always indexed, so the index is only missing if it couldn't be
+        if self.entry(filename).is_none() {
+            return Site::default();
+        }
 
-                if self.filename != filename {
-                    let link = ctx.link();
-                    link.send_message(utils::Message::UpdateData(filename));
-                    link.send_message(utils::Message::SetState(utils::FetchState::Pending));
-                    return yew::html!(<></>);
-                }
+        Site {
+            posts: Some(self.posts.iter().map(|post| post.filename.clone()).collect()),
+            tags: Some(self.posts.iter().flat_map(|post| post.tags.clone()).collect()),
+            ..Site::default()
+        }
+    }
 
-                let body = self
-                    .body
-                    .clone()
-                    .expect("body shouldn't be None while fetch_state is Complete");
-
-                let mut components = yew_markdown::CustomComponents::new();
-
-                components.register("UseTitle", |props| {
-                    let title: String = props.get_parsed("title")?;
-                    Ok(yew::html!( <Title title={title} /> ))
-                });
-                components.register("UseLink", |props| {
-                    let link: String = props.get_parsed("link")?;
-                    let text: String = props.get_parsed("text")?;
-                    let Some(route) = Route::recognize(&link) else {
-                        return Err("invalid path".into());
-                    };
-
-                    Ok(yew::html!( <Link<Route> to={route}>{text}</Link<Route>> ))
-                });
-
-                yew::html! {
-                    <div class={yew::classes!("post")}>
-                        <Markdown src={body} components={components}/>
-                    </div>
-                }
-            }
-            utils::FetchState::NotFound => yew::html!( <Redirect<Route> to={Route::NotFound} /> ),
-            utils::FetchState::Error(error_message) => {
-                yew::html!( <p class={yew::classes!("status", "error")}>{error_message}</p> )
-            }
-            utils::FetchState::Ongoing => {
-                yew::html!( <p class={yew::classes!("status")}>{"Fetching..."}</p> )
-            }
-            utils::FetchState::Pending => {
-                ctx.link().send_message(utils::Message::FetchData);
-                yew::html!(<></>)
+    fn view(&self, filename: &str, diagnostics: bool) -> yew::Html {
+        // rendered after the markdown so the front matter takes precedence over `UseTitle`
+        let title = self.entry(filename).map(|(_, entry)| {
+            yew::html! {
+                <Title
+                    title={entry.title.clone()}
+                    description={entry.description.clone()}
+                    image={entry.image.clone()}
+                    article=true
+                />
             }
+        });
+
+        let path = Route::Post {
+            filename: filename.to_owned(),
+        }
+        .to_path();
+        let findings = diagnostics.then(|| self.document.check(&self.site(filename), &path));
+
+        yew::html! {
+            <div class={yew::classes!("post")}>
+                { self.document.view(findings.as_deref()) }
+                { for title }
+                { self.navigation(filename) }
+            </div>
         }
     }
 }
 
+/// The browser can only scroll to the heading in the URL's fragment by itself if the post was
+/// prerendered, so it's done again once the post is shown.
+pub(super) fn scroll_to_fragment() {
+    let window = web_sys::window().unwrap();
+    let hash = window.location().hash().unwrap_or_default();
+
+    if let Some(element) = hash
+        .strip_prefix('#')
+        .filter(|id| !id.is_empty())
+        .and_then(|id| window.document()?.get_element_by_id(id))
+    {
+        element.scroll_into_view();
+    }
+}
+
+#[yew::function_component(PostView)]
+fn post_view(props: &Props) -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+    let diagnostics = diagnostics::use_enabled();
+
+    let content = fetch::use_fetch(
+        props.filename.clone(),
+        |filename| {
+            let path = format!("/files/{filename}");
+            let posts = fetch::seed(&prerender, &cache, index::PATH, index::parse)
+                .map(fetch::Seed::into_inner)
+                .unwrap_or_default();
+            let math = math::seed(&prerender, &cache, &path);
+            let highlighting = highlight::seed(&prerender, &cache, &path);
+
+            fetch::seed(&prerender, &cache, &path, |post| Some(post.to_owned())).map(|seed| {
+                seed.map(|post| Content::new(&post, posts, &math, &highlighting))
+            })
+        },
+        {
+            let cache = cache.clone();
+
+            move |filename| {
+                let cache = cache.clone();
+
+                async move {
+                    let path = format!("/files/{filename}");
+                    let post = cache.fetch(&path).await?;
+
+                    // the post is still readable without its metadata and navigation, so the
+                    // index is optional
+                    let posts = index::fetch(&cache).await.unwrap_or_default();
+                    let math = math::fetch(&cache, &path).await;
+                    let highlighting = highlight::fetch(&cache, &path).await;
+
+                    Ok(Content::new(&post, posts, &math, &highlighting))
+                }
+            }
+        },
+    );
+
+    let complete = matches!(content.status, fetch::Status::Complete(_));
+    yew::use_effect_with((props.filename.clone(), complete), |(_, complete)| {
+        if *complete {
+            scroll_to_fragment();
+        }
+    });
+
+    content.view(|content| content.view(&props.filename, diagnostics))
+}
+
 pub fn post(filename: String) -> yew::Html {
     yew::html!(<PostView filename={filename}/>)
 }
@@ -14,139 +14,154 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use serde::Deserialize;
+use std::collections::BTreeSet;
 
+use web_sys::HtmlInputElement;
+use yew::TargetCast;
+use yew_router::components::Link;
+
+use crate::cache::Cache;
 use crate::card::Card;
+use crate::fetch;
+use crate::index::{self, Post};
+use crate::prerender::Prerender;
+use crate::search::Search;
 use crate::title::Title;
-use crate::utils;
+use crate::{config, utils};
 use crate::Route;
 
-#[derive(Deserialize)]
-struct Post {
-    title: String,
-    date: String,
-    filename: String,
-}
-
-struct PostList {
-    posts: Vec<Post>,
-    fetch_state: utils::FetchState,
+#[derive(PartialEq, yew::Properties)]
+struct Props {
+    pub tag: Option<String>,
 }
 
-impl yew::Component for PostList {
-    type Message = utils::Message<Vec<Post>, utils::Never>;
-    type Properties = ();
+fn tag_list(posts: &[Post], current_tag: Option<&String>) -> yew::Html {
+    let tags = posts
+        .iter()
+        .flat_map(|post| post.tags.iter())
+        .collect::<BTreeSet<_>>();
 
-    fn create(_ctx: &yew::Context<Self>) -> Self {
-        Self {
-            posts: Vec::with_capacity(0),
-            fetch_state: utils::FetchState::Pending,
-        }
+    if tags.is_empty() {
+        return yew::html!(<></>);
     }
 
-    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
-        match msg {
-            utils::Message::FetchData => {
-                ctx.link().send_future(async move {
-                    let base = web_sys::window().unwrap().location().origin().unwrap();
-
-                    match reqwest::get(format!("{base}/posts.json"))
-                        .await
-                        .and_then(|response| response.error_for_status())
-                    {
-                        Err(error) => {
-                            if let Some(reqwest::StatusCode::NOT_FOUND) = error.status() {
-                                return utils::Message::SetContent(Vec::new());
-                            }
-
-                            utils::Message::SetState(utils::FetchState::Error(
-                                error.to_string(),
-                            ))
-                        }
-                        Ok(response) => match response.json().await {
-                            Err(error) => {
-                                utils::Message::SetState(utils::FetchState::Error(
-                                    error.to_string(),
-                                ))
-                            }
-                            Ok(posts) => utils::Message::SetContent(posts),
-                        },
-                    }
-                });
-
-                self.fetch_state = utils::FetchState::Ongoing;
-                true
-            }
-            utils::Message::SetContent(posts) => {
-                self.posts = posts;
+    let links = tags.into_iter().map(|tag| {
+        let mut classes = yew::classes!("tag");
+        if current_tag == Some(tag) {
+            classes.push("active");
+        }
 
-                self.fetch_state = utils::FetchState::Complete;
-                true
-            }
-            utils::Message::SetState(state) => {
-                self.fetch_state = state;
-                true
-            }
-            _ => unreachable!(), // Message::UpdateData is never sent
+        yew::html! {
+            <Link<Route> classes={classes} to={Route::Tag { tag: tag.clone() }}>
+                {format!("#{tag}")}
+            </Link<Route>>
         }
+    });
+
+    let mut all_classes = yew::classes!("tag");
+    if current_tag.is_none() {
+        all_classes.push("active");
     }
 
-    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
-        match &self.fetch_state {
-            utils::FetchState::Complete => {
-                if self.posts.is_empty() {
-                    return yew::html! {
-                        <>
-                            <Title title="Posts" />
-                            <p>{"Nothing to see here."}</p>
-                        </>
-                    };
-                }
+    yew::html! {
+        <div class={yew::classes!("tag-list")}>
+            <Link<Route> classes={all_classes} to={Route::Posts}>{"all"}</Link<Route>>
+            { for links }
+        </div>
+    }
+}
 
-                let cards = self.posts.iter().map(|post| {
-                    yew::html!(
-                        <Card
-                            title={post.title.clone()}
-                            url={utils::Url::Internal(Route::Post { filename: post.filename.clone() })}
-                            subtext={post.date.clone()}
-                        />
-                    )
-                });
-
-                yew::html! {
-                    <>
-                        <Title title="Posts" />
-                        <div class={yew::classes!("card-grid")}>
-                            { for cards }
-                        </div>
-                    </>
-                }
+#[yew::function_component(PostList)]
+fn post_list(props: &Props) -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+    let query = yew::use_state(String::new);
+
+    let posts = fetch::use_fetch(
+        (),
+        |_| fetch::seed(&prerender, &cache, index::PATH, index::parse),
+        {
+            let cache = cache.clone();
+
+            move |_| {
+                let cache = cache.clone();
+                async move { index::fetch(&cache).await }
             }
-            utils::FetchState::Error(error_message) => {
-                yew::html! {
-                    <>
-                        <Title title="Posts" />
-                        <p class={yew::classes!("status", "error")}>{error_message}</p>
-                    </>
-                }
+        },
+    );
+
+    let tag = props.tag.as_ref();
+    let title = match tag {
+        None => "Posts".to_owned(),
+        Some(tag) => format!("Posts tagged #{tag}"),
+    };
+
+    let content = posts.view(|posts| {
+        let search_box = config::SEARCH.then(|| {
+            yew::html! {
+                <input
+                    class={yew::classes!("search")}
+                    type="search"
+                    placeholder="Search posts"
+                    value={(*query).clone()}
+                    oninput={
+                        let query = query.clone();
+                        move |event: yew::InputEvent| {
+                            query.set(event.target_unchecked_into::<HtmlInputElement>().value())
+                        }
+                    }
+                />
             }
-            utils::FetchState::Ongoing => {
-                yew::html! {
-                    <>
-                        <Title title="Posts" />
-                        <p class={yew::classes!("status")}>{"Fetching..."}</p>
-                    </>
+        });
+
+        // the search index is cached, so remounting Search after the query is cleared is cheap
+        if config::SEARCH && !query.trim().is_empty() {
+            return yew::html! {
+                <>
+                    { for search_box }
+                    <Search query={(*query).clone()} />
+                </>
+            };
+        }
+
+        let cards = posts
+            .iter()
+            .filter(|post| tag.is_none_or(|tag| post.tags.contains(tag)))
+            .map(|post| {
+                yew::html!(
+                    <Card
+                        title={post.title.clone()}
+                        url={utils::Url::Internal(Route::Post { filename: post.filename.clone() })}
+                        subtext={post.date.clone()}
+                        tags={post.tags.clone()}
+                    />
+                )
+            })
+            .collect::<Vec<_>>();
+
+        yew::html! {
+            <>
+                { for search_box }
+                { tag_list(posts, tag) }
+                if cards.is_empty() {
+                    <p>{"Nothing to see here."}</p>
+                } else {
+                    <div class={yew::classes!("card-grid")}>
+                        { for cards }
+                    </div>
                 }
-            }
-            utils::FetchState::Pending => {
-                ctx.link().send_message(utils::Message::FetchData);
-                yew::html!( <Title title="Posts" /> )
-            }
-            _ => unreachable!(), // FetchState::NotFound is never set as fetch_state
+            </>
         }
+    });
+
+    yew::html! {
+        <>
+            <Title title={title} />
+            { content }
+        </>
     }
 }
 
-pub fn posts() -> yew::Html {
-    yew::html!(<PostList />)
+pub fn posts(tag: Option<String>) -> yew::Html {
+    yew::html!(<PostList tag={tag} />)
 }
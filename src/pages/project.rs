@@ -0,0 +1,149 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use yew_router::Routable;
+
+use crate::cache::Cache;
+use crate::diagnostics::{self, Site};
+use crate::document::Document;
+use crate::fetch;
+use crate::highlight::{self, Highlighting};
+use crate::math;
+use crate::prerender::Prerender;
+use crate::title::Title;
+use crate::Route;
+
+use super::post;
+use super::projects::{self, Project};
+
+#[derive(PartialEq)]
+struct Content {
+    document: Document,
+    project: Project,
+}
+
+#[derive(PartialEq, yew::Properties)]
+struct Props {
+    pub name: String,
+}
+
+impl Content {
+    fn new(
+        name: &str,
+        write_up: &str,
+        projects: Option<Vec<Project>>,
+        math: &[math::Rendered],
+        highlighting: &[Highlighting],
+    ) -> Self {
+        let project = projects
+            .into_iter()
+            .flatten()
+            .find(|project| project.name == name)
+            .unwrap_or_else(|| Project::unlisted(name));
+
+        Self {
+            document: Document::new(write_up, math, highlighting),
+            project,
+        }
+    }
+
+    fn view(&self, diagnostics: bool) -> yew::Html {
+        // a write-up only knows about its own project
+        let path = Route::Project {
+            name: self.project.name.clone(),
+        }
+        .to_path();
+        let findings = diagnostics.then(|| self.document.check(&Site::default(), &path));
+        let links = self.project.links().into_iter().map(|(title, url)| {
+            yew::html!( <li><a href={url}>{title}</a></li> )
+        });
+
+        // rendered before the markdown so `UseTitle` takes precedence over it
+        yew::html! {
+            <div class={yew::classes!("post")}>
+                <Title
+                    title={self.project.name.clone()}
+                    description={self.project.description.clone()}
+                />
+                { self.document.view(findings.as_deref()) }
+                <nav class={yew::classes!("project-links")}>
+                    <ul>
+                        { for links }
+                    </ul>
+                </nav>
+            </div>
+        }
+    }
+}
+
+fn path(name: &str) -> String {
+    format!("/files/projects/{name}.md")
+}
+
+#[yew::function_component(ProjectView)]
+fn project_view(props: &Props) -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+    let diagnostics = diagnostics::use_enabled();
+
+    let content = fetch::use_fetch(
+        props.name.clone(),
+        |name| {
+            let projects = projects::seed_listed(&prerender, &cache);
+            let math = math::seed(&prerender, &cache, &path(name));
+            let highlighting = highlight::seed(&prerender, &cache, &path(name));
+
+            fetch::seed(&prerender, &cache, &path(name), |write_up| Some(write_up.to_owned())).map(
+                |seed| {
+                    seed.map(|write_up| {
+                        Content::new(name, &write_up, projects, &math, &highlighting)
+                    })
+                },
+            )
+        },
+        {
+            let cache = cache.clone();
+
+            move |name| {
+                let cache = cache.clone();
+
+                async move {
+                    let write_up = cache.fetch(&path(&name)).await?;
+
+                    // the write-up is still readable without the project's details
+                    let projects = projects::fetch_listed(&cache).await;
+                    let math = math::fetch(&cache, &path(&name)).await;
+                    let highlighting = highlight::fetch(&cache, &path(&name)).await;
+
+                    Ok(Content::new(&name, &write_up, Some(projects), &math, &highlighting))
+                }
+            }
+        },
+    );
+
+    let complete = matches!(content.status, fetch::Status::Complete(_));
+    yew::use_effect_with((props.name.clone(), complete), |(_, complete)| {
+        if *complete {
+            post::scroll_to_fragment();
+        }
+    });
+
+    content.view(|content| content.view(diagnostics))
+}
+
+pub fn project(name: String) -> yew::Html {
+    yew::html!(<ProjectView name={name}/>)
+}
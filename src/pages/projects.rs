@@ -14,168 +14,324 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>
 
+use std::collections::BTreeSet;
+use std::fmt;
+
 use web_sys::js_sys::Date;
 
 use serde::Deserialize;
 
-use crate::card::Card;
+use crate::cache::Cache;
+use crate::card::{self, Card};
+use crate::fetch;
+use crate::prerender::Prerender;
 use crate::title::Title;
-use crate::{config, utils};
+use crate::{config, utils, Route};
+
+const PAGES_PATH: &str = "/project-pages.json";
+const PATH: &str = "/projects.json";
 
 #[derive(Deserialize)]
 struct Error {
     detail: String,
 }
 
+/// Star and fork counts, which GitHub abbreviates (e.g. `1.2k`) on the pages they get scraped from.
+#[derive(PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Count {
+    Number(u64),
+    Text(String),
+}
+
 #[derive(PartialEq, Deserialize)]
-struct Project {
-    author: String,
-    name: String,
+struct Link {
+    title: String,
+    url: String,
 }
 
-struct ProjectList {
+/// The projects along with the names of those with a write-up of their own.
+#[derive(PartialEq)]
+struct Listing {
+    pages: BTreeSet<String>,
     projects: Vec<Project>,
-    fetch_state: utils::FetchState,
+}
+
+/// Either one of the repositories pinned on GitHub, or one listed in the self-hosted
+/// `projects.json`, which has everything but the author.
+#[derive(Default, PartialEq, Deserialize)]
+pub(super) struct Project {
+    #[serde(default)]
+    author: Option<String>,
+    pub(super) name: String,
+    #[serde(default)]
+    pub(super) description: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default, alias = "languageColor")]
+    language_color: Option<String>,
+    #[serde(default)]
+    stars: Option<Count>,
+    #[serde(default)]
+    forks: Option<Count>,
+    /// A calendar date, e.g. `2024-05-01`.
+    #[serde(default)]
+    updated: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    links: Vec<Link>,
+    #[serde(default)]
+    screenshots: Vec<String>,
+    #[serde(default)]
+    featured: bool,
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(count) => write!(f, "{count}"),
+            Self::Text(count) => write!(f, "{count}"),
+        }
+    }
+}
+
+/// GitHub's colours for the languages most likely to be listed without one.
+fn language_color(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "C" => "#555555",
+        "C++" => "#f34b7d",
+        "Go" => "#00add8",
+        "HTML" => "#e34c26",
+        "Java" => "#b07219",
+        "JavaScript" => "#f1e05a",
+        "Nix" => "#7e7eff",
+        "Python" => "#3572a5",
+        "Rust" => "#dea584",
+        "Shell" => "#89e051",
+        "TypeScript" => "#3178c6",
+        _ => return None,
+    })
 }
 
 impl Project {
-    fn to_card(&self) -> yew::Html {
-        let full_name = format!("{}/{}", self.author, self.name);
+    /// A stand-in for a project that isn't listed, e.g. because it's pinned on GitHub.
+    pub(super) fn unlisted(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    fn full_name(&self) -> String {
+        let author = self.author.as_deref().unwrap_or(config::GITHUB_USERNAME);
+        format!("{}/{}", author, self.name)
+    }
+
+    /// The project's `(title, url)` links, its GitHub repository if it was listed without any.
+    pub(super) fn links(&self) -> Vec<(String, String)> {
+        if self.links.is_empty() {
+            let url = format!("https://github.com/{}", self.full_name());
+            return vec![("Repository".to_owned(), url)];
+        }
+
+        self.links
+            .iter()
+            .map(|link| (link.title.clone(), link.url.clone()))
+            .collect()
+    }
+
+    fn to_card(&self, has_page: bool) -> yew::Html {
+        let full_name = self.full_name();
+
+        let url = match has_page {
+            true => utils::Url::Internal(Route::Project {
+                name: self.name.clone(),
+            }),
+            false => {
+                let mut links = self.links();
+                utils::Url::External(links.swap_remove(0).1)
+            }
+        };
+
+        // pinned repositories come without screenshots, but GitHub renders a preview for them
+        let image_url = match (self.screenshots.first(), &self.author) {
+            (Some(screenshot), _) => Some(screenshot.clone()),
+            (None, Some(_)) => Some(format!(
+                "https://opengraph.githubassets.com/{}/{}",
+                Date::now() as u64 / (1000 * 60 * 5),
+                full_name,
+            )),
+            (None, None) => None,
+        };
 
-        let image_url = format!(
-            "https://opengraph.githubassets.com/{}/{}",
-            Date::now() as u64 / (1000 * 60 * 5),
-            full_name,
-        );
-        let url = format!("https://github.com/{}", full_name);
+        let language = self.language.as_ref().map(|language| card::Language {
+            name: language.clone(),
+            color: self
+                .language_color
+                .clone()
+                .or_else(|| language_color(language).map(str::to_owned)),
+        });
+
+        let tags = self.tags.iter().map(|tag| {
+            yew::html!( <small class={yew::classes!("tag")}>{format!("#{tag}")}</small> )
+        });
 
         yew::html!(
             <Card
                 title={self.name.clone()}
-                url={utils::Url::External(url)}
+                url={url}
+                description={self.description.clone()}
+                language={language}
+                stars={self.stars.as_ref().map(ToString::to_string)}
+                forks={self.forks.as_ref().map(ToString::to_string)}
+                updated={self.updated.clone()}
                 image_url={image_url}
-            />
+            >
+                if !self.tags.is_empty() {
+                    <div class={yew::classes!("card-tags")}>
+                        { for tags }
+                    </div>
+                }
+            </Card>
         )
     }
 }
 
-impl yew::Component for ProjectList {
-    type Message = utils::Message<Vec<Project>, utils::Never>;
-    type Properties = ();
+fn parse(projects: &str) -> serde_json::Result<Vec<Project>> {
+    let mut projects = serde_json::from_str::<Vec<Project>>(projects)?;
 
-    fn create(_ctx: &yew::Context<Self>) -> Self {
-        Self {
-            projects: Vec::with_capacity(0),
-            fetch_state: utils::FetchState::Pending,
-        }
+    // stable, so the projects otherwise keep their listed order
+    projects.sort_by_key(|project| !project.featured);
+
+    Ok(projects)
+}
+
+/// Fetches the self-hosted project list the generator writes from `projects.toml`.
+async fn fetch_self_hosted(cache: &Cache) -> Result<Vec<Project>, fetch::Error> {
+    match cache.fetch(PATH).await {
+        Err(error) => match fetch::Error::from(error) {
+            fetch::Error::NotFound => Ok(Vec::new()),
+            error => Err(error),
+        },
+        Ok(projects) => Ok(parse(&projects)?),
     }
+}
 
-    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
-        match msg {
-            utils::Message::FetchData => {
-                ctx.link().send_future(async {
-                    match reqwest::get(format!(
-                        "https://pinned.berrysauce.dev/get/{}",
-                        config::GITHUB_USERNAME
-                    ))
-                    .await
-                    {
-                        Err(error) => {
-                            utils::Message::SetState(utils::FetchState::Error(
-                                error.to_string(),
-                            ))
-                        }
-                        Ok(response) => {
-                            if response.status() != 200 {
-                                match response.json::<Error>().await {
-                                    Err(error) => {
-                                        return utils::Message::SetState(utils::FetchState::Error(
-                                            error.to_string(),
-                                        ));
-                                    }
-                                    Ok(error) => {
-                                        return utils::Message::SetState(utils::FetchState::Error(
-                                            error.detail,
-                                        ));
-                                    }
-                                }
-                            }
-
-                            match response.json().await {
-                                Err(error) => {
-                                    utils::Message::SetState(utils::FetchState::Error(
-                                        error.to_string(),
-                                    ))
-                                }
-                                Ok(projects) => utils::Message::SetContent(projects),
-                            }
-                        }
-                    }
-                });
-
-                ctx.link()
-                    .send_message(utils::Message::SetState(utils::FetchState::Ongoing));
-                true
-            }
-            utils::Message::SetContent(projects) => {
-                self.projects = projects;
+/// Fetches the repositories pinned on GitHub through `pinned.berrysauce.dev`.
+async fn fetch_pinned() -> Result<Vec<Project>, fetch::Error> {
+    let response = reqwest::get(format!(
+        "https://pinned.berrysauce.dev/get/{}",
+        config::GITHUB_USERNAME
+    ))
+    .await?;
 
-                self.fetch_state = utils::FetchState::Complete;
-                true
-            }
-            utils::Message::SetState(state) => {
-                self.fetch_state = state;
-                true
-            }
-            _ => unreachable!(), // Message::UpdateData is never sent
-        }
+    let status = response.status();
+    if status != 200 {
+        return Err(match response.json::<Error>().await {
+            Err(_) => fetch::Error::Status(status.as_u16()),
+            Ok(error) => fetch::Error::Upstream(error.detail),
+        });
     }
 
-    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
-        match &self.fetch_state {
-            utils::FetchState::Complete => {
-                if self.projects.is_empty() {
-                    return yew::html! {
-                        <>
-                            <Title title="Projects" />
-                            <p>{"Nothing to see here."}</p>
-                        </>
-                    };
-                }
+    Ok(response.json().await?)
+}
 
-                let cards = self.projects.iter().map(|project| project.to_card());
+/// The projects with a write-up, none if the list couldn't be fetched since the cards can still
+/// link to the projects themselves.
+async fn fetch_pages(cache: &Cache) -> BTreeSet<String> {
+    match cache.fetch(PAGES_PATH).await {
+        Err(_) => BTreeSet::new(),
+        Ok(pages) => serde_json::from_str(&pages).unwrap_or_default(),
+    }
+}
 
-                yew::html! {
-                    <>
-                        <Title title="Projects" />
-                        <div class={yew::classes!("card-grid")}>
-                            { for cards }
-                        </div>
-                    </>
-                }
-            }
-            utils::FetchState::Error(error_message) => {
-                yew::html! {
-                    <>
-                        <Title title="Projects" />
-                        <p class={yew::classes!("status", "error")}>{error_message}</p>
-                    </>
-                }
+/// The self-hosted project list if it was prerendered or cached, and none when the projects come
+/// from GitHub.
+pub(super) fn seed_listed(prerender: &Prerender, cache: &Cache) -> Option<Vec<Project>> {
+    if !config::SELF_HOSTED_PROJECTS {
+        return None;
+    }
+
+    fetch::seed(prerender, cache, PATH, |projects| parse(projects).ok())
+        .map(fetch::Seed::into_inner)
+}
+
+/// Fetches the self-hosted project list, leaving it empty when the projects come from GitHub, as
+/// fetching the pinned repositories isn't worth it for a single project.
+pub(super) async fn fetch_listed(cache: &Cache) -> Vec<Project> {
+    if !config::SELF_HOSTED_PROJECTS {
+        return Vec::new();
+    }
+
+    fetch_self_hosted(cache).await.unwrap_or_default()
+}
+
+#[yew::function_component(ProjectList)]
+fn project_list() -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+
+    let listing = fetch::use_fetch(
+        (),
+        |_| {
+            // pinned repositories are fetched from GitHub every time, so they're never seeded
+            if !config::SELF_HOSTED_PROJECTS {
+                return None;
             }
-            utils::FetchState::Ongoing => {
-                yew::html! {
-                    <>
-                        <Title title="Projects" />
-                        <p class={yew::classes!("status")}>{"Fetching..."}</p>
-                    </>
+
+            let pages = fetch::seed(&prerender, &cache, PAGES_PATH, |pages| {
+                serde_json::from_str(pages).ok()
+            })
+            .map(fetch::Seed::into_inner)
+            .unwrap_or_default();
+
+            fetch::seed(&prerender, &cache, PATH, |projects| parse(projects).ok())
+                .map(|seed| seed.map(|projects| Listing { pages, projects }))
+        },
+        {
+            let cache = cache.clone();
+
+            move |_| {
+                let cache = cache.clone();
+
+                async move {
+                    let projects = match config::SELF_HOSTED_PROJECTS {
+                        true => fetch_self_hosted(&cache).await?,
+                        false => fetch_pinned().await?,
+                    };
+
+                    Ok(Listing {
+                        pages: fetch_pages(&cache).await,
+                        projects,
+                    })
                 }
             }
-            utils::FetchState::Pending => {
-                ctx.link().send_message(utils::Message::FetchData);
-                yew::html!( <Title title="Projects" /> )
-            }
-            _ => unreachable!(), // FetchState::NotFound is never set as fetch_state
+        },
+    );
+
+    let content = listing.view(|listing| {
+        if listing.projects.is_empty() {
+            return yew::html!( <p>{"Nothing to see here."}</p> );
         }
+
+        let cards = listing
+            .projects
+            .iter()
+            .map(|project| project.to_card(listing.pages.contains(&project.name)));
+
+        yew::html! {
+            <div class={yew::classes!("card-grid")}>
+                { for cards }
+            </div>
+        }
+    });
+
+    yew::html! {
+        <>
+            <Title title="Projects" />
+            { content }
+        </>
     }
 }
 
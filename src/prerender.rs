@@ -0,0 +1,180 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[cfg(feature = "ssr")]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::title::Head;
+#[cfg(feature = "ssr")]
+use crate::Route;
+
+#[cfg(any(feature = "hydration", feature = "ssr"))]
+const DATA_ELEMENT_ID: &str = "prerender-data";
+
+/// What a prerendered page embeds for the app hydrating it.
+#[derive(Default, Deserialize, Serialize)]
+struct Data {
+    path: String,
+    responses: HashMap<String, String>,
+}
+
+/// Provided as a context both while prerendering and while hydrating, so components can render
+/// from the responses the page was prerendered with instead of fetching them again.
+#[derive(Clone, Default)]
+pub struct Prerender {
+    data: Rc<Data>,
+    head: Rc<RefCell<Option<Head>>>,
+}
+
+impl PartialEq for Prerender {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.data, &other.data) && Rc::ptr_eq(&self.head, &other.head)
+    }
+}
+
+impl Prerender {
+    #[cfg(feature = "hydration")]
+    pub fn from_document(document: &web_sys::Document) -> Self {
+        let data = document
+            .get_element_by_id(DATA_ELEMENT_ID)
+            .and_then(|element| element.text_content())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        Self {
+            data: Rc::new(data),
+            head: Rc::default(),
+        }
+    }
+
+    /// Trailing slashes are ignored, as static hosts tend to add them when serving `index.html`
+    /// files.
+    #[cfg(feature = "hydration")]
+    pub fn is_for_current_location(&self) -> bool {
+        let path = web_sys::window().unwrap().location().pathname().unwrap();
+
+        !self.data.path.is_empty()
+            && self.data.path.trim_end_matches('/') == path.trim_end_matches('/')
+    }
+
+    /// Looks up the response for `path` the page was prerendered with.
+    pub fn response(&self, path: &str) -> Option<String> {
+        self.data.responses.get(path).cloned()
+    }
+
+    /// Records the head of the page being prerendered.
+    pub fn set_head(&self, head: Head) {
+        let _ = self.head.borrow_mut().insert(head);
+    }
+}
+
+/// A route to prerender, along with the responses its components would otherwise fetch.
+#[cfg(feature = "ssr")]
+pub struct Page {
+    pub route: Route,
+    pub responses: HashMap<String, String>,
+}
+
+#[cfg(feature = "ssr")]
+impl Page {
+    /// Where the page goes relative to the output directory, so that static hosts serve it for
+    /// its route.
+    pub fn file_path(&self) -> PathBuf {
+        use yew_router::Routable;
+
+        match self.route {
+            Route::NotFound => PathBuf::from("404.html"),
+            _ => PathBuf::from(self.route.to_path().trim_start_matches('/')).join("index.html"),
+        }
+    }
+
+    /// Renders the page into `template`, the `index.html` produced by trunk, blocking until done.
+    pub fn render(self, template: &str) -> String {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let template = template.to_owned();
+
+        // components may spawn local tasks, which need a yew runtime to run on
+        yew::platform::Runtime::default().spawn_pinned(move || async move {
+            let _ = sender.send(self.render_local(&template).await);
+        });
+
+        receiver.recv().expect("rendering task shouldn't panic")
+    }
+
+    async fn render_local(self, template: &str) -> String {
+        use stylist::manager::{render_static, StyleManager};
+        use yew_router::Routable;
+
+        let path = self.route.to_path();
+        let prerender = Prerender {
+            data: Rc::new(Data {
+                path: path.clone(),
+                responses: self.responses,
+            }),
+            head: Rc::default(),
+        };
+
+        let (writer, reader) = render_static();
+
+        let body = {
+            let prerender = prerender.clone();
+
+            yew::LocalServerRenderer::<crate::ServerApp>::with_props(crate::ServerAppProps {
+                manager: StyleManager::builder()
+                    .writer(writer)
+                    .build()
+                    .expect("style manager should build with a static writer"),
+                prerender,
+                url: path,
+            })
+            .render()
+            .await
+        };
+
+        let mut head = String::new();
+        reader
+            .read_style_data()
+            .write_static_markup(&mut head)
+            .expect("writing to a string shouldn't fail");
+
+        // `</` is escaped so the data can't close the script element early
+        let data = serde_json::to_string(&*prerender.data)
+            .expect("prerender data should be serializable")
+            .replace("</", "<\\/");
+        head.push_str(&format!(
+            "<script id=\"{DATA_ELEMENT_ID}\" type=\"application/json\">{data}</script>"
+        ));
+
+        let mut html = template.to_owned();
+
+        if let Some(page_head) = prerender.head.borrow().as_ref() {
+            if let (Some(start), Some(end)) = (html.find("<title>"), html.find("</title>")) {
+                html.replace_range(start..end + "</title>".len(), "");
+            }
+
+            head.insert_str(0, &page_head.markup());
+        }
+
+        html.replacen("</head>", &format!("{head}</head>"), 1)
+            .replacen("<body>", &format!("<body>{body}"), 1)
+    }
+}
@@ -0,0 +1,239 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+
+use crate::cache::Cache;
+use crate::card::Card;
+use crate::fetch;
+use crate::prerender::Prerender;
+use crate::utils;
+use crate::Route;
+
+const PATH: &str = "/search.json";
+const SNIPPET_CONTEXT: usize = 40;
+const SNIPPET_LENGTH: usize = 160;
+
+#[derive(Deserialize, PartialEq)]
+struct Document {
+    title: String,
+    date: String,
+    filename: String,
+    tags: Vec<String>,
+    /// The start of the post's text, cut short by the generator to keep the index small.
+    excerpt: String,
+}
+
+#[derive(Default, Deserialize, PartialEq)]
+pub struct SearchIndex {
+    documents: Vec<Document>,
+    terms: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+struct Query {
+    tokens: Vec<String>,
+}
+
+#[derive(PartialEq, yew::Properties)]
+pub struct Props {
+    pub query: String,
+}
+
+/// Splits `text` into the lowercase terms the index is keyed by, used for both the posts and
+/// the query.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Splits `text` into its words, keeping their byte offsets.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+impl Query {
+    fn new(query: &str) -> Self {
+        Self {
+            tokens: tokenize(query).collect(),
+        }
+    }
+
+    /// The last token is matched as a prefix, since it's likely still being typed.
+    fn matches(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+
+        self.tokens.iter().enumerate().any(|(index, token)| {
+            word == *token || (index == self.tokens.len() - 1 && word.starts_with(token.as_str()))
+        })
+    }
+}
+
+impl SearchIndex {
+    /// Ranks the documents containing every token of the query by the sum of their term weights,
+    /// scaled by how rare each term is.
+    fn search(&self, query: &Query) -> Vec<&Document> {
+        let mut scores = HashMap::<usize, f64>::new();
+
+        for (index, token) in query.tokens.iter().enumerate() {
+            let is_last = index == query.tokens.len() - 1;
+            let mut token_scores = HashMap::<usize, f64>::new();
+
+            let postings = self
+                .terms
+                .range(token.clone()..)
+                .take_while(|(term, _)| *term == token || (is_last && term.starts_with(token.as_str())));
+
+            for (_, postings) in postings {
+                let rarity = (self.documents.len() as f64 / postings.len() as f64).ln() + 1.0;
+
+                for &(document, weight) in postings {
+                    *token_scores.entry(document).or_default() += weight as f64 * rarity;
+                }
+            }
+
+            if index == 0 {
+                scores = token_scores;
+                continue;
+            }
+
+            scores.retain(|document, score| match token_scores.get(document) {
+                None => false,
+                Some(token_score) => {
+                    *score += token_score;
+                    true
+                }
+            });
+        }
+
+        let mut results = scores.into_iter().collect::<Vec<_>>();
+        results.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        results
+            .into_iter()
+            .filter_map(|(document, _)| self.documents.get(document))
+            .collect()
+    }
+}
+
+impl Document {
+    /// Cuts a window of the excerpt around the first match, highlighting every matching word in
+    /// it. Matches past the excerpt leave the window at its start.
+    fn snippet(&self, query: &Query) -> yew::Html {
+        let text = self.excerpt.as_str();
+        let first_match = words(text)
+            .find(|(_, word)| query.matches(word))
+            .map_or(0, |(offset, _)| offset);
+
+        let start = text[..first_match]
+            .char_indices()
+            .rev()
+            .nth(SNIPPET_CONTEXT)
+            .map_or(0, |(offset, _)| offset);
+        let end = text[start..]
+            .char_indices()
+            .nth(SNIPPET_LENGTH)
+            .map_or(text.len(), |(offset, _)| start + offset);
+        let window = &text[start..end];
+
+        let mut parts = Vec::new();
+        let mut last = 0;
+
+        for (offset, word) in words(window).filter(|(_, word)| query.matches(word)) {
+            parts.push(yew::html!({ &window[last..offset] }));
+            parts.push(yew::html!( <mark>{ word }</mark> ));
+            last = offset + word.len();
+        }
+        parts.push(yew::html!({ &window[last..] }));
+
+        yew::html! {
+            <p class={yew::classes!("card-snippet")}>
+                if start > 0 { {"…"} }
+                { for parts }
+                if end < text.len() { {"…"} }
+            </p>
+        }
+    }
+}
+
+#[yew::function_component(Search)]
+pub fn search(props: &Props) -> yew::Html {
+    let cache = yew::use_context::<Cache>().unwrap_or_default();
+    let prerender = yew::use_context::<Prerender>().unwrap_or_default();
+
+    let index = fetch::use_fetch(
+        (),
+        |_| {
+            fetch::seed(&prerender, &cache, PATH, |index| {
+                serde_json::from_str::<SearchIndex>(index).ok()
+            })
+        },
+        {
+            let cache = cache.clone();
+
+            move |_| {
+                let cache = cache.clone();
+
+                async move {
+                    match cache.fetch(PATH).await {
+                        Err(error) => match fetch::Error::from(error) {
+                            fetch::Error::NotFound => Ok(SearchIndex::default()),
+                            error => Err(error),
+                        },
+                        Ok(index) => Ok(serde_json::from_str(&index)?),
+                    }
+                }
+            }
+        },
+    );
+
+    let query = Query::new(&props.query);
+
+    if query.tokens.is_empty() {
+        return yew::html!(<></>);
+    }
+
+    index.view(|index| {
+        let results = index.search(&query);
+
+        if results.is_empty() {
+            return yew::html!( <p>{"No posts match your search."}</p> );
+        }
+
+        let cards = results.into_iter().map(|document| {
+            yew::html! {
+                <Card
+                    title={document.title.clone()}
+                    url={utils::Url::Internal(Route::Post { filename: document.filename.clone() })}
+                    subtext={document.date.clone()}
+                    tags={document.tags.clone()}
+                >
+                    { document.snippet(&query) }
+                </Card>
+            }
+        });
+
+        yew::html! {
+            <div class={yew::classes!("card-grid")}>
+                { for cards }
+            </div>
+        }
+    })
+}
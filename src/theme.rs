@@ -0,0 +1,229 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use web_sys::js_sys::Date;
+use yew::ContextProvider;
+
+use crate::config::{self, Palette};
+
+/// The attribute on the root element that the stylesheets pick the theme by.
+const ATTRIBUTE: &str = "data-theme";
+/// Where the picked theme is kept between visits, which the script in `index.html` reads to apply
+/// it before the app has loaded.
+const STORAGE_KEY: &str = "theme";
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ThemeContext {
+    pub theme: Theme,
+    pub palette: &'static Palette,
+    set: yew::Callback<Theme>,
+}
+
+#[derive(PartialEq, yew::Properties)]
+pub struct Props {
+    pub children: yew::Html,
+}
+
+impl Theme {
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Light => Some("light"),
+            Self::Dark => Some("dark"),
+            Self::System => None,
+        }
+    }
+
+    /// The theme after this one in the toggle's cycle.
+    fn next(self) -> Self {
+        match self {
+            Self::Light => Self::Dark,
+            Self::Dark => Self::System,
+            Self::System => Self::Light,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+            Self::System => "the system's",
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Light => "fa-solid fa-sun",
+            Self::Dark => "fa-solid fa-moon",
+            Self::System => "fa-solid fa-circle-half-stroke",
+        }
+    }
+
+    fn stored() -> Self {
+        let storage = web_sys::window().unwrap().local_storage().ok().flatten();
+
+        let stored = storage.and_then(|storage| storage.get_item(STORAGE_KEY).ok().flatten());
+
+        match stored.as_deref() {
+            Some("light") => Self::Light,
+            Some("dark") => Self::Dark,
+            _ => Self::System,
+        }
+    }
+
+    /// Marks the root element for the stylesheets and remembers the theme, storage being
+    /// unavailable in some private windows.
+    fn apply(self) {
+        let window = web_sys::window().unwrap();
+        let root = window.document().and_then(|document| document.document_element());
+        let storage = window.local_storage().ok().flatten();
+
+        match self.name() {
+            None => {
+                if let Some(root) = root {
+                    let _ = root.remove_attribute(ATTRIBUTE);
+                }
+                if let Some(storage) = storage {
+                    let _ = storage.remove_item(STORAGE_KEY);
+                }
+            }
+            Some(name) => {
+                if let Some(root) = root {
+                    let _ = root.set_attribute(ATTRIBUTE, name);
+                }
+                if let Some(storage) = storage {
+                    let _ = storage.set_item(STORAGE_KEY, name);
+                }
+            }
+        }
+    }
+}
+
+/// The palette in season, if any, which is only known once the app runs in the browser.
+fn seasonal_palette() -> Option<&'static Palette> {
+    let month = Date::new_0().get_month() + 1;
+
+    config::PALETTES
+        .iter()
+        .find(|palette| palette.months.contains(&month))
+}
+
+/// Hands the palette's colours to the stylesheets.
+fn palette_style(palette: &Palette) -> stylist::StyleSource {
+    stylist::css!(
+        ":root {\
+            --background: ${background};\
+            --foreground: ${foreground};\
+            --primary: ${primary};\
+            --secondary-light: ${secondary_light};\
+            --secondary-dark: ${secondary_dark};\
+            --error: ${error};\
+        }",
+        background = palette.background,
+        foreground = palette.foreground,
+        primary = palette.primary,
+        secondary_light = palette.secondary_light,
+        secondary_dark = palette.secondary_dark,
+        error = palette.error,
+    )
+}
+
+impl Default for ThemeContext {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            palette: &config::PALETTE,
+            set: yew::Callback::default(),
+        }
+    }
+}
+
+impl ThemeContext {
+    pub fn set(&self, theme: Theme) {
+        self.set.emit(theme);
+    }
+}
+
+#[yew::function_component(ThemeProvider)]
+pub fn theme_provider(props: &Props) -> yew::Html {
+    // starts out following the system with the default palette so hydration sees what was
+    // prerendered
+    let theme = yew::use_state(Theme::default);
+    let palette = yew::use_state(|| &config::PALETTE);
+
+    {
+        let theme = theme.clone();
+        let palette = palette.clone();
+
+        yew_hooks::use_mount(move || {
+            theme.set(Theme::stored());
+
+            if let Some(seasonal_palette) = seasonal_palette() {
+                palette.set(seasonal_palette);
+            }
+        });
+    }
+
+    let context = ThemeContext {
+        theme: *theme,
+        palette: *palette,
+        set: yew::Callback::from(move |new_theme: Theme| {
+            new_theme.apply();
+            theme.set(new_theme);
+        }),
+    };
+
+    yew::html! {
+        <ContextProvider<ThemeContext> context={context}>
+            // the default palette comes from palette.css, so the page has its colours before the
+            // app runs
+            if palette.name != config::PALETTE.name {
+                <stylist::yew::Global css={palette_style(*palette)} />
+            }
+            { props.children.clone() }
+        </ContextProvider<ThemeContext>>
+    }
+}
+
+/// Cycles through the light, dark and system themes.
+#[yew::function_component(ThemeToggle)]
+pub fn theme_toggle() -> yew::Html {
+    let context = yew::use_context::<ThemeContext>().unwrap_or_default();
+    let theme = context.theme;
+    let label = format!(
+        "Using {} theme, switch to {} theme",
+        theme.label(),
+        theme.next().label()
+    );
+
+    yew::html! {
+        <button
+            class={yew::classes!("theme-toggle")}
+            aria-label={label.clone()}
+            title={label}
+            onclick={move |_| context.set(theme.next())}
+        >
+            <i class={theme.icon()} aria-hidden="true"></i>
+        </button>
+    }
+}
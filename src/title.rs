@@ -14,13 +14,166 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use yew_router::hooks::use_location;
+
+use crate::config;
+use crate::prerender::Prerender;
+
 #[derive(Clone, PartialEq, yew::Properties)]
 pub struct Props {
     pub title: String,
+    #[prop_or_default]
+    pub description: Option<String>,
+    #[prop_or_default]
+    pub image: Option<String>,
+    /// Describes the page as an article rather than a website to link previews.
+    #[prop_or_default]
+    pub article: bool,
+}
+
+/// The title and meta tags describing a page, for browsers, crawlers and link previews.
+#[derive(Clone, PartialEq)]
+pub struct Head {
+    title: String,
+    description: String,
+    image: Option<String>,
+    kind: &'static str,
+    url: String,
+}
+
+impl Head {
+    fn new(props: &Props, path: &str) -> Self {
+        Self {
+            title: props.title.clone(),
+            description: props
+                .description
+                .clone()
+                .unwrap_or_else(|| config::DESCRIPTION.to_owned()),
+            image: props.image.as_deref().map(absolute),
+            kind: if props.article { "article" } else { "website" },
+            url: absolute(path),
+        }
+    }
+
+    /// The `(attribute, key, content)` of every meta tag managed here, the content being `None`
+    /// when the tag shouldn't be present on the page.
+    fn meta(&self) -> [(&'static str, &'static str, Option<&str>); 11] {
+        let description = Some(self.description.as_str());
+        let image = self.image.as_deref();
+        let title = Some(self.title.as_str());
+
+        [
+            ("name", "theme-color", Some(config::THEME_COLOR)),
+            ("name", "description", description),
+            ("property", "og:title", title),
+            ("property", "og:description", description),
+            ("property", "og:type", Some(self.kind)),
+            ("property", "og:url", Some(&self.url)),
+            ("property", "og:image", image),
+            (
+                "name",
+                "twitter:card",
+                Some(match image {
+                    None => "summary",
+                    Some(_) => "summary_large_image",
+                }),
+            ),
+            ("name", "twitter:title", title),
+            ("name", "twitter:description", description),
+            ("name", "twitter:image", image),
+        ]
+    }
+
+    /// Updates the head of the document in place, as navigating doesn't reload the page.
+    fn apply(&self) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let Some(head) = document.head() else {
+            return;
+        };
+
+        let set =
+            |tag: &str, attribute: &str, key: &str, value_attribute: &str, value: Option<&str>| {
+                let existing = document
+                    .query_selector(&format!("{tag}[{attribute}=\"{key}\"]"))
+                    .ok()
+                    .flatten();
+
+                match (existing, value) {
+                    (None, None) => (),
+                    (None, Some(value)) => {
+                        let Ok(element) = document.create_element(tag) else {
+                            return;
+                        };
+
+                        let _ = element.set_attribute(attribute, key);
+                        let _ = element.set_attribute(value_attribute, value);
+                        let _ = head.append_child(&element);
+                    }
+                    (Some(element), None) => element.remove(),
+                    (Some(element), Some(value)) => {
+                        let _ = element.set_attribute(value_attribute, value);
+                    }
+                }
+            };
+
+        set("link", "rel", "canonical", "href", Some(&self.url));
+
+        for (attribute, key, content) in self.meta() {
+            set("meta", attribute, key, "content", content);
+        }
+    }
+
+    /// Renders the head as markup, for the prerenderer to put in place of the template's title.
+    #[cfg(feature = "ssr")]
+    pub fn markup(&self) -> String {
+        let mut markup = format!(
+            "<title>{}</title><link rel=\"canonical\" href=\"{}\" />",
+            escape(&self.title),
+            escape(&self.url),
+        );
+
+        for (attribute, key, content) in self.meta() {
+            if let Some(content) = content {
+                markup.push_str(&format!(
+                    "<meta {attribute}=\"{key}\" content=\"{}\" />",
+                    escape(content)
+                ));
+            }
+        }
+
+        markup
+    }
 }
 
 #[yew::function_component(Title)]
 pub fn title(props: &Props) -> yew::Html {
+    let location = use_location();
+    let head = Head::new(
+        props,
+        location.as_ref().map_or("/", |location| location.path()),
+    );
+
+    if let Some(prerender) = yew::use_context::<Prerender>() {
+        prerender.set_head(head.clone());
+    }
+
     yew_hooks::use_title(props.title.clone());
+    yew::use_effect_with(head, |head| head.apply());
     yew::html!(<></>)
 }
+
+/// Link previews need absolute URLs, so paths are resolved against the URL the site is hosted at.
+fn absolute(url: &str) -> String {
+    match url.starts_with('/') {
+        false => url.to_owned(),
+        true => format!("{}{url}", config::URL),
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
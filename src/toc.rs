@@ -0,0 +1,159 @@
+// web segment - a personal website used to host some markdown files and my portfolio
+// Copyright (C) 2023  Segmentation Violator
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+/// A heading of a post, as listed by its table of contents.
+#[derive(Clone, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub slug: String,
+    pub text: String,
+}
+
+/// Gives every heading in `source` a permalink by appending a `UseAnchor` component to it,
+/// returning the modified source along with the headings found.
+pub fn anchor_headings(source: &str) -> (String, Vec<Heading>) {
+    let mut headings = Vec::new();
+    let mut insertions = Vec::new();
+    // every slug given out, by how many times it has been suffixed
+    let mut slugs = HashMap::<String, usize>::new();
+
+    // (level, text, end of the heading's content)
+    let mut current: Option<(usize, String, Option<usize>)> = None;
+
+    for (event, range) in Parser::new(source).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((level as usize, String::new(), None));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                // empty headings have nowhere to put an anchor
+                let Some((level, text, Some(end))) = current.take() else {
+                    continue;
+                };
+
+                // suffixed until it's unused, as the suffix could make it another heading's slug,
+                // e.g. a second "Intro" taking "Intro 1"'s
+                let base = slugify(&text);
+                let mut slug = base.clone();
+                while slugs.contains_key(&slug) {
+                    let count = slugs.entry(base.clone()).or_default();
+                    *count += 1;
+                    slug = format!("{base}-{count}");
+                }
+                slugs.insert(slug.clone(), 0);
+
+                insertions.push((end, format!(" <UseAnchor id=\"{slug}\"/>")));
+                headings.push(Heading {
+                    level,
+                    slug,
+                    text: text.trim().to_owned(),
+                });
+            }
+            event => {
+                if let Some((_, text, end)) = current.as_mut() {
+                    if let Event::Text(content) | Event::Code(content) = event {
+                        text.push_str(&content);
+                    }
+                    let _ = end.insert(range.end);
+                }
+            }
+        }
+    }
+
+    let mut source = source.to_owned();
+    for (offset, anchor) in insertions.into_iter().rev() {
+        source.insert_str(offset, &anchor);
+    }
+
+    (source, headings)
+}
+
+fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        if character.is_ascii_alphanumeric() {
+            slug.push(character.to_ascii_lowercase());
+        } else if (character.is_whitespace() || character == '-' || character == '_')
+            && !slug.is_empty()
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+
+    match slug.trim_end_matches('-') {
+        "" => "section".to_owned(),
+        slug => slug.to_owned(),
+    }
+}
+
+#[derive(PartialEq, yew::Properties)]
+pub struct Props {
+    pub headings: Vec<Heading>,
+}
+
+/// Lists the headings of a post below its title, nesting each under the heading before it with a
+/// lower level.
+#[yew::function_component(TableOfContents)]
+pub fn table_of_contents(props: &Props) -> yew::Html {
+    let headings = props
+        .headings
+        .iter()
+        .filter(|heading| heading.level > 1)
+        .collect::<Vec<_>>();
+
+    if headings.is_empty() {
+        return yew::html!(<></>);
+    }
+
+    yew::html! {
+        <nav class={yew::classes!("toc")}>
+            { list(&headings) }
+        </nav>
+    }
+}
+
+fn list(headings: &[&Heading]) -> yew::Html {
+    let mut items = Vec::new();
+    let mut index = 0;
+
+    while index < headings.len() {
+        let heading = headings[index];
+        let nested = headings[index + 1..]
+            .iter()
+            .take_while(|child| child.level > heading.level)
+            .count();
+        let children = &headings[index + 1..index + 1 + nested];
+
+        items.push(yew::html! {
+            <li>
+                <a href={format!("#{}", heading.slug)}>{ heading.text.clone() }</a>
+                if !children.is_empty() {
+                    { list(children) }
+                }
+            </li>
+        });
+
+        index += 1 + nested;
+    }
+
+    yew::html!( <ul>{ for items }</ul> )
+}
@@ -1,24 +1,33 @@
 use crate::Route;
 
-pub enum FetchState {
-    Complete,
-    Error(String),
-    NotFound,
-    Ongoing,
-    Pending,
-}
-
-pub enum Message<T, D> {
-    FetchData,
-    SetContent(T),
-    SetState(FetchState),
-    UpdateData(D),
-}
-
-pub enum Never {}
+pub const FRONT_MATTER_DELIMITER: &str = "+++";
 
 #[derive(Clone, PartialEq)]
 pub enum Url {
     External(String),
     Internal(Route),
 }
+
+/// Splits a page into the `+++` fenced front matter block that the generator reads post metadata
+/// from and the rest of it.
+pub fn split_front_matter(source: &str) -> Option<(&str, &str)> {
+    let rest = source.strip_prefix(FRONT_MATTER_DELIMITER)?;
+    let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_DELIMITER {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
+
+/// Strips the front matter off a page, if it has any.
+pub fn strip_front_matter(source: &str) -> &str {
+    split_front_matter(source).map_or(source, |(_, body)| body)
+}